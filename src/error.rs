@@ -0,0 +1,82 @@
+use crate::{future::TimeoutError, http::HttpError, runtime::JoinError};
+use std::{error::Error, fmt, io};
+
+/// Top-level error for everything the runtime can fail with. Each subsystem
+/// keeps its own error type and converts into this one through `From`, so a
+/// function returning `Result<_, RuntimeError>` can use `?` on any of them.
+///
+/// A timeout shows up as either of two variants, depending on what set it:
+/// `Timeout` for `future::timeout`, and `Http(HttpError::Timeout)` for the
+/// timeouts of an HTTP request, e.g. `Http::get_timeout`. `is_timeout`
+/// checks for both.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RuntimeError {
+    /// An I/O error that didn't come from a more specific subsystem, e.g.
+    /// a `TcpStream` that failed to connect.
+    Io(io::Error),
+    /// A request made through `Http` or `HttpClient` failed, including by
+    /// running into one of its own timeouts.
+    Http(HttpError),
+    /// A spawned task didn't complete, e.g. because it was aborted.
+    Join(JoinError),
+    /// A future bounded with `future::timeout` didn't complete in time.
+    Timeout(TimeoutError),
+}
+
+impl RuntimeError {
+    /// Whether this is a timeout, either from `future::timeout` or from an
+    /// HTTP request's own.
+    pub fn is_timeout(&self) -> bool {
+        matches!(
+            self,
+            RuntimeError::Timeout(_) | RuntimeError::Http(HttpError::Timeout)
+        )
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Io(e) => write!(f, "I/O error: {e}"),
+            RuntimeError::Http(e) => write!(f, "HTTP error: {e}"),
+            RuntimeError::Join(e) => write!(f, "join error: {e}"),
+            RuntimeError::Timeout(e) => write!(f, "timeout: {e}"),
+        }
+    }
+}
+
+impl Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RuntimeError::Io(e) => Some(e),
+            RuntimeError::Http(e) => Some(e),
+            RuntimeError::Join(e) => Some(e),
+            RuntimeError::Timeout(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for RuntimeError {
+    fn from(e: io::Error) -> Self {
+        RuntimeError::Io(e)
+    }
+}
+
+impl From<HttpError> for RuntimeError {
+    fn from(e: HttpError) -> Self {
+        RuntimeError::Http(e)
+    }
+}
+
+impl From<JoinError> for RuntimeError {
+    fn from(e: JoinError) -> Self {
+        RuntimeError::Join(e)
+    }
+}
+
+impl From<TimeoutError> for RuntimeError {
+    fn from(e: TimeoutError) -> Self {
+        RuntimeError::Timeout(e)
+    }
+}
@@ -0,0 +1,18 @@
+//! Combinators for running futures together and adapting their outputs.
+//! They work with any `std::future::Future`, so they can be used on either
+//! executor.
+
+pub use ext::{FutureExt, Map, Then};
+pub use join::{join, join_all, Join, JoinAll};
+pub use race::{race, Either, Race};
+pub use ready::{pending, ready, Pending, Ready};
+pub use tasks::{Next, Tasks};
+pub use timeout::{timeout, Timeout, TimeoutError};
+
+mod ext;
+mod join;
+mod maybe_done;
+mod race;
+mod ready;
+mod tasks;
+mod timeout;
@@ -0,0 +1,113 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Adapters for any `Future`, so its output can be transformed or chained
+/// into another future inline instead of in a hand-written state machine.
+pub trait FutureExt: Future {
+    /// Resolves to `f` applied to the output of this future.
+    fn map<U, F>(self, f: F) -> Map<Self, F>
+    where
+        F: FnOnce(Self::Output) -> U,
+        Self: Sized,
+    {
+        Map {
+            future: Box::pin(self),
+            f: Some(f),
+        }
+    }
+
+    /// Once this future completes, passes its output to `f` and resolves to
+    /// the output of the future `f` returns.
+    fn then<Fut, F>(self, f: F) -> Then<Self, Fut, F>
+    where
+        F: FnOnce(Self::Output) -> Fut,
+        Fut: Future,
+        Self: Sized,
+    {
+        Then {
+            state: ThenState::First(Box::pin(self), Some(f)),
+        }
+    }
+
+    /// Boxes and pins this future, erasing its type. Handy for storing
+    /// futures of different types in the same place.
+    fn boxed<'a>(self) -> Pin<Box<dyn Future<Output = Self::Output> + 'a>>
+    where
+        Self: Sized + 'a,
+    {
+        Box::pin(self)
+    }
+}
+
+impl<T: Future + ?Sized> FutureExt for T {}
+
+/// Future returned by `FutureExt::map`.
+pub struct Map<Fut, F> {
+    future: Pin<Box<Fut>>,
+    // `None` once it has been applied
+    f: Option<F>,
+}
+
+// The future is pinned in its box and the closure is never pinned at all.
+impl<Fut, F> Unpin for Map<Fut, F> {}
+
+impl<U, Fut, F> Future for Map<Fut, F>
+where
+    Fut: Future,
+    F: FnOnce(Fut::Output) -> U,
+{
+    type Output = U;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let output = match self.future.as_mut().poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        let f = self.f.take().expect("`Map` polled after completion");
+        Poll::Ready(f(output))
+    }
+}
+
+/// Future returned by `FutureExt::then`.
+pub struct Then<Fut1, Fut2, F> {
+    state: ThenState<Fut1, Fut2, F>,
+}
+
+enum ThenState<Fut1, Fut2, F> {
+    // Waiting for the first future, with the closure to call on its output
+    First(Pin<Box<Fut1>>, Option<F>),
+    // Waiting for the future returned by the closure
+    Second(Pin<Box<Fut2>>),
+}
+
+// Both futures are pinned in their boxes and the closure is never pinned.
+impl<Fut1, Fut2, F> Unpin for Then<Fut1, Fut2, F> {}
+
+impl<Fut1, Fut2, F> Future for Then<Fut1, Fut2, F>
+where
+    Fut1: Future,
+    Fut2: Future,
+    F: FnOnce(Fut1::Output) -> Fut2,
+{
+    type Output = Fut2::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        loop {
+            match &mut self.state {
+                ThenState::First(future, f) => {
+                    let output = match future.as_mut().poll(cx) {
+                        Poll::Ready(output) => output,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    let f = f.take().expect("`Then` polled after completion");
+                    // Drops the first future before the second one starts
+                    self.state = ThenState::Second(Box::pin(f(output)));
+                }
+                ThenState::Second(future) => return future.as_mut().poll(cx),
+            }
+        }
+    }
+}
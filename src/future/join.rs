@@ -0,0 +1,78 @@
+use super::maybe_done::MaybeDone;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Runs `a` and `b` concurrently and resolves to both of their outputs once
+/// both have completed. Whichever finishes first has its output kept until
+/// the other one is done too.
+pub fn join<A, B>(a: A, b: B) -> Join<A, B>
+where
+    A: Future,
+    B: Future,
+{
+    Join {
+        a: MaybeDone::new(a),
+        b: MaybeDone::new(b),
+    }
+}
+
+/// Future returned by `join`.
+pub struct Join<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+impl<A: Future, B: Future> Future for Join<A, B> {
+    type Output = (A::Output, B::Output);
+
+    /// Polls whichever of the two futures is still pending, each with the
+    /// task's `Waker`, so the task is woken when either can make progress.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let a_done = self.a.poll_done(cx);
+        let b_done = self.b.poll_done(cx);
+        if !(a_done && b_done) {
+            return Poll::Pending;
+        }
+        Poll::Ready((self.a.take(), self.b.take()))
+    }
+}
+
+/// Runs all of `futures` concurrently and resolves to their outputs once
+/// every one of them has completed. The outputs are in the same order as
+/// `futures`, however the futures happen to finish.
+pub fn join_all<F: Future>(futures: Vec<F>) -> JoinAll<F> {
+    JoinAll {
+        remaining: futures.len(),
+        slots: futures.into_iter().map(MaybeDone::new).collect(),
+    }
+}
+
+/// Future returned by `join_all`.
+pub struct JoinAll<F: Future> {
+    // One per future, in the order they were given
+    slots: Vec<MaybeDone<F>>,
+    // Number of futures that haven't completed yet
+    remaining: usize,
+}
+
+impl<F: Future> Future for JoinAll<F> {
+    type Output = Vec<F::Output>;
+
+    /// Polls only the futures that are still pending. Ones that completed
+    /// earlier just keep their output until the rest are done.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        for slot in &mut this.slots {
+            if matches!(slot, MaybeDone::Pending(_)) && slot.poll_done(cx) {
+                this.remaining -= 1;
+            }
+        }
+        if this.remaining > 0 {
+            return Poll::Pending;
+        }
+        Poll::Ready(this.slots.iter_mut().map(MaybeDone::take).collect())
+    }
+}
@@ -0,0 +1,47 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A future that's polled alongside others, holding on to its output once
+/// it's done until the combinator it's part of is ready to hand it out.
+/// The future is boxed so the combinator stays `Unpin` whatever it holds.
+pub(super) enum MaybeDone<F: Future> {
+    Pending(Pin<Box<F>>),
+    Done(F::Output),
+    // The output has been taken
+    Gone,
+}
+
+// The future is pinned in its box and the output is never pinned at all.
+impl<F: Future> Unpin for MaybeDone<F> {}
+
+impl<F: Future> MaybeDone<F> {
+    pub(super) fn new(future: F) -> Self {
+        MaybeDone::Pending(Box::pin(future))
+    }
+
+    /// Polls the future if it's still pending and returns `true` once it
+    /// has completed. The future is dropped as soon as it's done.
+    pub(super) fn poll_done(&mut self, cx: &mut Context) -> bool {
+        let MaybeDone::Pending(future) = self else {
+            return true;
+        };
+        match future.as_mut().poll(cx) {
+            Poll::Ready(output) => {
+                *self = MaybeDone::Done(output);
+                true
+            }
+            Poll::Pending => false,
+        }
+    }
+
+    /// Takes the output. Must only be called once `poll_done` returned `true`.
+    pub(super) fn take(&mut self) -> F::Output {
+        match std::mem::replace(self, MaybeDone::Gone) {
+            MaybeDone::Done(output) => output,
+            _ => panic!("output taken before the future completed"),
+        }
+    }
+}
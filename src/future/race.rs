@@ -0,0 +1,58 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Output of `race`: the output of whichever future finished first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// Runs `a` and `b` concurrently and resolves to the output of whichever
+/// completes first. The other one is dropped right away, which releases
+/// whatever it held, e.g. its reactor registration. If both are ready on
+/// the same poll, `a` wins.
+pub fn race<A, B>(a: A, b: B) -> Race<A, B>
+where
+    A: Future,
+    B: Future,
+{
+    Race {
+        a: Some(Box::pin(a)),
+        b: Some(Box::pin(b)),
+    }
+}
+
+/// Future returned by `race`.
+pub struct Race<A, B> {
+    // Both `None` once one of them has won
+    a: Option<Pin<Box<A>>>,
+    b: Option<Pin<Box<B>>>,
+}
+
+impl<A: Future, B: Future> Future for Race<A, B> {
+    type Output = Either<A::Output, B::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let (Some(a), Some(b)) = (this.a.as_mut(), this.b.as_mut()) else {
+            panic!("`Race` polled after completion");
+        };
+
+        let output = if let Poll::Ready(output) = a.as_mut().poll(cx) {
+            Either::Left(output)
+        } else if let Poll::Ready(output) = b.as_mut().poll(cx) {
+            Either::Right(output)
+        } else {
+            return Poll::Pending;
+        };
+
+        // Drops the loser along with the winner
+        this.a = None;
+        this.b = None;
+        Poll::Ready(output)
+    }
+}
@@ -0,0 +1,49 @@
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Completes with `value` the first time it's polled.
+pub fn ready<T>(value: T) -> Ready<T> {
+    Ready { value: Some(value) }
+}
+
+/// Future returned by `ready`.
+pub struct Ready<T> {
+    // `None` once it has been handed out
+    value: Option<T>,
+}
+
+// The value is never pinned.
+impl<T> Unpin for Ready<T> {}
+
+impl<T> Future for Ready<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(self.value.take().expect("`Ready` polled after completion"))
+    }
+}
+
+/// Never completes. Its `Waker` isn't stored anywhere, so nothing is ever
+/// going to wake a task waiting on just this.
+pub fn pending<T>() -> Pending<T> {
+    Pending {
+        _output: PhantomData,
+    }
+}
+
+/// Future returned by `pending`.
+pub struct Pending<T> {
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<T> Future for Pending<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Pending
+    }
+}
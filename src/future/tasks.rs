@@ -0,0 +1,139 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// A set of futures that yields their outputs in the order they complete,
+/// rather than waiting for all of them like `join_all` does.
+///
+/// Works like a small executor of its own: every future gets an id and a
+/// `Waker` that queues that id, so `next` only polls the futures that were
+/// woken since the last time.
+pub struct Tasks<F> {
+    futures: HashMap<usize, Pin<Box<F>>>,
+    ready: Arc<ReadyQueue>,
+    next_id: usize,
+}
+
+struct ReadyQueue {
+    // Ids of the futures woken since they were last polled
+    ids: Mutex<VecDeque<usize>>,
+    // `Waker` of the task waiting in `next`, woken along with any future
+    waker: Mutex<Option<Waker>>,
+}
+
+impl<F: Future> Tasks<F> {
+    pub fn new() -> Self {
+        Self {
+            futures: HashMap::new(),
+            ready: Arc::new(ReadyQueue {
+                ids: Mutex::new(VecDeque::new()),
+                waker: Mutex::new(None),
+            }),
+            next_id: 0,
+        }
+    }
+
+    /// Adds `future` to the set. It's first polled by the next call to
+    /// `next`.
+    pub fn push(&mut self, future: F) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.futures.insert(id, Box::pin(future));
+        self.ready.ids.lock().unwrap().push_back(id);
+    }
+
+    /// Number of futures that haven't completed yet.
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Resolves to the output of the next future to complete, or to `None`
+    /// once the set is empty.
+    // Can't be `Iterator::next`, the output has to be waited for
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Next<'_, F> {
+        Next { tasks: self }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context) -> Poll<Option<F::Output>> {
+        if self.futures.is_empty() {
+            return Poll::Ready(None);
+        }
+        // Stored before polling so a wake during the poll isn't missed
+        *self.ready.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        // Only the futures woken so far. A future that wakes itself while
+        // being polled is left for the next poll, so it can't keep this
+        // one from ever returning.
+        let woken = self.ready.ids.lock().unwrap().len();
+        for _ in 0..woken {
+            let Some(id) = self.ready.ids.lock().unwrap().pop_front() else {
+                break;
+            };
+            // Guard against false wakeups, the future may be done already
+            let Some(future) = self.futures.get_mut(&id) else {
+                continue;
+            };
+
+            let waker: Waker = Arc::new(TaskWaker {
+                id,
+                ready: self.ready.clone(),
+            })
+            .into();
+            if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+                self.futures.remove(&id);
+                return Poll::Ready(Some(output));
+            }
+        }
+
+        if !self.ready.ids.lock().unwrap().is_empty() {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
+
+impl<F: Future> Default for Tasks<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Future returned by `Tasks::next`.
+pub struct Next<'a, F> {
+    tasks: &'a mut Tasks<F>,
+}
+
+impl<F: Future> Future for Next<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.tasks.poll_next(cx)
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.ids.lock().unwrap().push_back(self.id);
+        if let Some(waker) = self.ready.waker.lock().unwrap().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+}
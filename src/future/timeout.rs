@@ -0,0 +1,48 @@
+use super::{race, Either, Race};
+use crate::runtime::{self, Sleep};
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The future passed to `timeout` didn't complete in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError;
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "future timed out")
+    }
+}
+
+impl Error for TimeoutError {}
+
+/// Resolves to the output of `future`, or to `TimeoutError` if it hasn't
+/// completed once `duration` has passed. It's raced against a `sleep`, so
+/// on a timeout `future` is dropped along with whatever it held.
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        race: race(future, runtime::sleep(duration)),
+    }
+}
+
+/// Future returned by `timeout`.
+pub struct Timeout<F> {
+    race: Race<F, Sleep>,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, TimeoutError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.race).poll(cx) {
+            Poll::Ready(Either::Left(output)) => Poll::Ready(Ok(output)),
+            Poll::Ready(Either::Right(())) => Poll::Ready(Err(TimeoutError)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -1,96 +1,1168 @@
+pub use body::BodyStream;
+pub use cookie::CookieJar;
+pub use query::Query;
+pub use response::Response;
+
 use crate::runtime::{self, reactor};
+use chunked::ChunkedDecoder;
+use connection::{Connection, Connector};
+use dns::ResolveFuture;
 use mio::Interest;
+use pool::Pool;
 use std::{
-    future::Future,
-    io::{ErrorKind, Read, Write}, 
+    error::Error,
+    fmt,
+    future::{self, Future},
+    io::{self, ErrorKind, Read},
     pin::Pin,
-    task::{Context, Poll}
+    string::FromUtf8Error,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
-fn get_req(path: &str) -> String {
-    format!(
-        "GET {path} HTTP/1.1\r\n\
-             Host: localhost\r\n\
-             Connection: close\r\n\
-             \r\n"
-    )
+mod base64;
+mod body;
+mod chunked;
+mod connection;
+mod cookie;
+mod dns;
+#[cfg(feature = "gzip")]
+mod encoding;
+mod pool;
+mod query;
+mod redirect;
+mod response;
+#[cfg(feature = "tls")]
+mod tls;
+
+/// Everything that can go wrong while performing a request.
+#[derive(Debug)]
+pub enum HttpError {
+    /// Couldn't connect to the server, e.g. the connection was refused.
+    Connect(io::Error),
+    /// I/O error while writing the request or reading the response.
+    Io(io::Error),
+    /// The body was requested as text but isn't valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+    /// The response couldn't be parsed, e.g. it has no valid status line.
+    MalformedResponse(String),
+    /// The response didn't complete within the timeout set for the request.
+    Timeout,
+    /// Still redirected after following the given number of redirects.
+    TooManyRedirects(usize),
+    /// The server kept responding with the given `5xx` status, even after
+    /// the request was retried.
+    Status(u16),
+    /// More of the response had to be buffered than the given limit allows.
+    ResponseTooLarge(usize),
+    /// A header with the given name can't be sent, since its name or value
+    /// holds a line break or its name is empty. Sent as is, it would end the
+    /// header early and let the rest pass for headers of its own.
+    InvalidHeader(String),
+    /// A body couldn't be serialized to or deserialized from JSON.
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The body was compressed with the given `Content-Encoding`, which
+    /// can't be decoded.
+    #[cfg(feature = "gzip")]
+    UnsupportedEncoding(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Connect(e) => write!(f, "failed to connect: {e}"),
+            HttpError::Io(e) => write!(f, "I/O error: {e}"),
+            HttpError::InvalidUtf8(e) => write!(f, "body is not valid UTF-8: {e}"),
+            HttpError::MalformedResponse(msg) => write!(f, "malformed response: {msg}"),
+            HttpError::Timeout => write!(f, "request timed out"),
+            HttpError::TooManyRedirects(max) => write!(f, "more than {max} redirects"),
+            HttpError::Status(status) => write!(f, "server responded with {status}"),
+            HttpError::ResponseTooLarge(max) => write!(f, "response larger than {max} bytes"),
+            HttpError::InvalidHeader(name) => write!(f, "invalid header: {name:?}"),
+            #[cfg(feature = "json")]
+            HttpError::Json(e) => write!(f, "invalid JSON: {e}"),
+            #[cfg(feature = "gzip")]
+            HttpError::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported content encoding: {encoding}")
+            }
+        }
+    }
+}
+
+impl Error for HttpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpError::Connect(e) | HttpError::Io(e) => Some(e),
+            HttpError::InvalidUtf8(e) => Some(e),
+            #[cfg(feature = "json")]
+            HttpError::Json(e) => Some(e),
+            #[cfg(feature = "gzip")]
+            HttpError::UnsupportedEncoding(_) => None,
+            HttpError::MalformedResponse(_)
+            | HttpError::Timeout
+            | HttpError::TooManyRedirects(_)
+            | HttpError::Status(_)
+            | HttpError::ResponseTooLarge(_)
+            | HttpError::InvalidHeader(_) => None,
+        }
+    }
+}
+
+/// Formats a complete HTTP/1.1 request. `Host` (set to `host`) and
+/// `Connection` are always sent, followed by `headers` in order.
+/// `Content-Length` is only added when there is a `body` and `headers` don't
+/// set it already, so verbs like `DELETE` can leave it out entirely. Fails
+/// with `HttpError::InvalidHeader` for a header that would break the request
+/// apart.
+fn build_request(
+    method: &str,
+    host: &str,
+    path: &str,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+    keep_alive: bool,
+) -> Result<Vec<u8>, HttpError> {
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    let mut req = format!(
+        "{method} {path} HTTP/1.1\r\n\
+             Host: {host}\r\n\
+             Connection: {connection}\r\n"
+    );
+
+    let breaks_line = |s: &str| s.contains(['\r', '\n']);
+    for (name, value) in headers {
+        if name.trim().is_empty() || breaks_line(name) || breaks_line(value) {
+            return Err(HttpError::InvalidHeader(name.clone()));
+        }
+        req.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    let has_length = headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("content-length"));
+    if let Some(body) = body.filter(|_| !has_length) {
+        req.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+
+    req.push_str("\r\n");
+    let mut req = req.into_bytes();
+    req.extend_from_slice(body.unwrap_or_default());
+    Ok(req)
+}
+
+/// Address used by the `Http` shorthands.
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// Limit on how much of a response is buffered, unless set otherwise with
+/// `RequestBuilder::max_response_bytes`.
+const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Longest `get_retry` waits before a retry, however many came before.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long `get_retry` waits before retry `retry`, counted from 0:
+/// `base_delay * 2^retry`, capped at `MAX_BACKOFF`.
+fn backoff(base_delay: Duration, retry: u32) -> Duration {
+    2u32.checked_pow(retry)
+        .and_then(|factor| base_delay.checked_mul(factor))
+        .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
 }
 
+/// Shorthands that send requests to the server at `127.0.0.1:8080`. Use an
+/// `HttpClient` to talk to a different address.
 pub struct Http;
 
 impl Http {
-    pub fn get(path: &str) -> impl Future<Output = String> {
-        HttpGetFuture::new(path.to_string())
+    pub fn get(path: &str) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().get(path)
+    }
+
+    /// Like `get`, but returns the raw body bytes instead of a `String`.
+    pub fn get_bytes(path: &str) -> impl Future<Output = Result<Vec<u8>, HttpError>> {
+        HttpClient::default().get_bytes(path)
+    }
+
+    /// Like `get`, but returns the status and headers along with the body.
+    pub fn get_response(path: &str) -> impl Future<Output = Result<Response, HttpError>> {
+        HttpClient::default().get_response(path)
+    }
+
+    /// Like `get`, but lets the body be processed chunk by chunk as it
+    /// arrives through a `BodyStream`.
+    pub fn get_stream(path: &str) -> impl Future<Output = Result<BodyStream, HttpError>> {
+        HttpClient::default().get_stream(path)
+    }
+
+    /// Like `get`, but fails with `HttpError::Timeout` if the response hasn't
+    /// been received within `timeout`.
+    pub fn get_timeout(
+        path: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().get_timeout(path, timeout)
+    }
+
+    /// Like `get_response`, but follows `3xx` redirects to the URL in their
+    /// `Location` header. Fails with `HttpError::TooManyRedirects` if the
+    /// response is still a redirect after `max_hops` of them, which also
+    /// guards against redirect loops.
+    pub fn get_following_redirects(
+        path: &str,
+        max_hops: usize,
+    ) -> impl Future<Output = Result<Response, HttpError>> {
+        HttpClient::default().get_following_redirects(path, max_hops)
+    }
+
+    /// Like `get`, but retries up to `attempts` times when connecting fails
+    /// or the server responds with a `5xx` status. Before retry `n`, counted
+    /// from 0, it waits `base_delay * 2^n`, but never more than a minute.
+    /// Fails with the error of the last try, which for a `5xx` status is
+    /// `HttpError::Status`.
+    pub fn get_retry(
+        path: &str,
+        attempts: u32,
+        base_delay: Duration,
+    ) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().get_retry(path, attempts, base_delay)
+    }
+
+    /// Sends `body` as the payload of a `POST` request. `Content-Length` is
+    /// always set, so an empty body is sent as `Content-Length: 0`.
+    pub fn post(path: &str, body: &[u8]) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().post(path, body)
+    }
+
+    /// Sends `body` as the payload of a `PUT` request.
+    pub fn put(path: &str, body: &[u8]) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().put(path, body)
+    }
+
+    /// Sends a `DELETE` request. No body and no `Content-Length` is sent.
+    pub fn delete(path: &str) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::default().delete(path)
+    }
+
+    /// Starts building a request with custom headers and/or a body.
+    pub fn request(method: &str, path: &str) -> RequestBuilder {
+        HttpClient::default().request(method, path)
+    }
+
+    /// Sends a `GET` request over TLS to `host` on port 443.
+    #[cfg(feature = "tls")]
+    pub fn get_tls(host: &str, path: &str) -> impl Future<Output = Result<String, HttpError>> {
+        HttpClient::new_tls(host).get(path)
+    }
+}
+
+/// Sends requests to the server at `addr`, e.g. `"127.0.0.1:3000"`. The
+/// `Host` header of every request is set to that same address.
+#[derive(Clone)]
+pub struct HttpClient {
+    addr: String,
+    // Sent as the `Host` header
+    host: String,
+    // Name to verify the server's certificate against when using TLS
+    server_name: Option<String>,
+    connect_timeout: Option<Duration>,
+    // Shared by every clone when keep-alive is enabled
+    pool: Option<Arc<Pool>>,
+    cookies: Option<CookieJar>,
+    #[cfg(feature = "gzip")]
+    auto_decompress: bool,
+}
+
+impl HttpClient {
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            host: addr.to_string(),
+            server_name: None,
+            connect_timeout: None,
+            pool: None,
+            cookies: None,
+            #[cfg(feature = "gzip")]
+            auto_decompress: false,
+        }
+    }
+
+    /// Sends requests over TLS to `host` on port 443. The server's
+    /// certificate must be valid for `host`.
+    #[cfg(feature = "tls")]
+    pub fn new_tls(host: &str) -> Self {
+        Self {
+            addr: format!("{host}:443"),
+            host: host.to_string(),
+            server_name: Some(host.to_string()),
+            connect_timeout: None,
+            pool: None,
+            cookies: None,
+            #[cfg(feature = "gzip")]
+            auto_decompress: false,
+        }
+    }
+
+    /// Fails requests with a `HttpError::Connect` error of kind `TimedOut`
+    /// if the TCP connection isn't established within `timeout`, counted
+    /// from the first poll so the address lookup is included. This is
+    /// separate from the timeout for receiving the response, and bounds how
+    /// long we wait on a peer that never completes the handshake.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Keeps connections open between requests, sending
+    /// `Connection: keep-alive` instead of `Connection: close`. A connection
+    /// goes back to the pool once a response whose end is known from
+    /// `Content-Length` or chunking has been read in full, and the next
+    /// request to the same address reuses it. Connections idle for longer
+    /// than `idle_timeout` are closed. Clones of this client share the pool.
+    pub fn keep_alive(mut self, idle_timeout: Duration) -> Self {
+        self.pool = Some(Arc::new(Pool::new(idle_timeout)));
+        self
+    }
+
+    /// Records the cookies servers set in `jar`, and sends the ones that
+    /// match each request along with it. A `Cookie` header added to a
+    /// request by hand takes the place of the jar's cookies.
+    pub fn cookie_jar(mut self, jar: CookieJar) -> Self {
+        self.cookies = Some(jar);
+        self
+    }
+
+    /// Whether to ask for compressed bodies with
+    /// `Accept-Encoding: gzip, deflate` and inflate them again before they're
+    /// handed out. It's off by default, the `gzip` feature only makes it
+    /// available. With it on, a body in any other encoding fails with
+    /// `HttpError::UnsupportedEncoding`. A request that sets its own
+    /// `Accept-Encoding` gets its body exactly as sent, as it does with this
+    /// turned off.
+    #[cfg(feature = "gzip")]
+    pub fn auto_decompress(mut self, enabled: bool) -> Self {
+        self.auto_decompress = enabled;
+        self
+    }
+
+    pub fn get(&self, path: &str) -> impl Future<Output = Result<String, HttpError>> {
+        self.request("GET", path).send()
+    }
+
+    pub fn get_bytes(&self, path: &str) -> impl Future<Output = Result<Vec<u8>, HttpError>> {
+        self.request("GET", path).send_bytes()
+    }
+
+    pub fn get_response(&self, path: &str) -> impl Future<Output = Result<Response, HttpError>> {
+        self.request("GET", path).send_response()
+    }
+
+    pub fn get_stream(&self, path: &str) -> impl Future<Output = Result<BodyStream, HttpError>> {
+        self.request("GET", path).send_stream()
+    }
+
+    pub fn get_timeout(
+        &self,
+        path: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<String, HttpError>> {
+        self.request("GET", path).timeout(timeout).send()
+    }
+
+    pub fn get_following_redirects(
+        &self,
+        path: &str,
+        max_hops: usize,
+    ) -> impl Future<Output = Result<Response, HttpError>> {
+        let mut client = self.clone();
+        let mut path = path.to_string();
+        async move {
+            for _ in 0..=max_hops {
+                let res = client.get_response(&path).await?;
+                if !redirect::is_redirect(res.status) {
+                    return Ok(res);
+                }
+
+                let location = res.header("location").ok_or_else(|| {
+                    HttpError::MalformedResponse(format!("{} without Location", res.status))
+                })?;
+                (client, path) = redirect::resolve(&client, &path, location)?;
+            }
+            Err(HttpError::TooManyRedirects(max_hops))
+        }
+    }
+
+    pub fn get_retry(
+        &self,
+        path: &str,
+        attempts: u32,
+        base_delay: Duration,
+    ) -> impl Future<Output = Result<String, HttpError>> {
+        let client = self.clone();
+        let path = path.to_string();
+        async move {
+            let mut retry = 0;
+            loop {
+                let error = match client.get_response(&path).await {
+                    Ok(res) if res.status >= 500 => HttpError::Status(res.status),
+                    Ok(res) => return String::from_utf8(res.body).map_err(HttpError::InvalidUtf8),
+                    Err(e @ HttpError::Connect(_)) => e,
+                    Err(e) => return Err(e),
+                };
+                if retry == attempts {
+                    return Err(error);
+                }
+                runtime::sleep(backoff(base_delay, retry)).await;
+                retry += 1;
+            }
+        }
+    }
+
+    pub fn post(&self, path: &str, body: &[u8]) -> impl Future<Output = Result<String, HttpError>> {
+        self.request("POST", path).body(body).send()
+    }
+
+    pub fn put(&self, path: &str, body: &[u8]) -> impl Future<Output = Result<String, HttpError>> {
+        self.request("PUT", path).body(body).send()
+    }
+
+    pub fn delete(&self, path: &str) -> impl Future<Output = Result<String, HttpError>> {
+        self.request("DELETE", path).send()
+    }
+
+    pub fn request(&self, method: &str, path: &str) -> RequestBuilder {
+        RequestBuilder {
+            client: self.clone(),
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![],
+            body: None,
+            timeout: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_ADDR)
+    }
+}
+
+/// Accumulates headers and an optional body for a request. Headers are sent
+/// in the order they were added, and adding the same name twice sends it
+/// twice rather than replacing the first value.
+pub struct RequestBuilder {
+    client: HttpClient,
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    max_response_bytes: usize,
+}
+
+impl RequestBuilder {
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: &[u8]) -> Self {
+        self.body = Some(body.to_vec());
+        self
+    }
+
+    /// Serializes `value` as the JSON body and sets `Content-Type` to match.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::Serialize>(self, value: &T) -> Result<Self, HttpError> {
+        let body = serde_json::to_vec(value).map_err(HttpError::Json)?;
+        Ok(self.header("Content-Type", "application/json").body(&body))
+    }
+
+    /// Authenticates with the `Basic` scheme, sending `user:pass` base64
+    /// encoded in the `Authorization` header.
+    pub fn basic_auth(self, user: &str, pass: &str) -> Self {
+        let credentials = base64::encode(format!("{user}:{pass}").as_bytes());
+        self.header("Authorization", &format!("Basic {credentials}"))
+    }
+
+    /// Authenticates with a bearer token in the `Authorization` header.
+    pub fn bearer(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {token}"))
+    }
+
+    /// Fails the request with `HttpError::Timeout` if the response hasn't
+    /// been received within `timeout`, counted from the first poll.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fails the request with `HttpError::ResponseTooLarge` once more than
+    /// `max` bytes of the response, head included, have to be buffered, so a
+    /// server that never stops sending can't exhaust memory. With
+    /// `send_stream` only the bytes not handed out yet count. Defaults to
+    /// 16 MiB.
+    pub fn max_response_bytes(mut self, max: usize) -> Self {
+        self.max_response_bytes = max;
+        self
+    }
+
+    /// Sends the request and returns the body as a `String`.
+    pub fn send(self) -> impl Future<Output = Result<String, HttpError>> {
+        let fut = self.send_bytes();
+        async move { String::from_utf8(fut.await?).map_err(HttpError::InvalidUtf8) }
+    }
+
+    /// Sends the request and returns the body bytes untouched, which is what
+    /// binary payloads like images need.
+    pub fn send_bytes(self) -> impl Future<Output = Result<Vec<u8>, HttpError>> {
+        let fut = self.send_response();
+        async move { Ok(fut.await?.body) }
+    }
+
+    /// Sends the request and parses the response.
+    pub fn send_response(self) -> impl Future<Output = Result<Response, HttpError>> {
+        let fut = self.into_http_future();
+        async move { fut?.await }
+    }
+
+    /// Sends the request and resolves once the response head has arrived.
+    /// The body can then be read incrementally from the returned `BodyStream`
+    /// instead of being buffered in full.
+    pub fn send_stream(self) -> impl Future<Output = Result<BodyStream, HttpError>> {
+        let fut = self.into_http_future();
+        async move {
+            let mut fut = fut?;
+            let response = future::poll_fn(|cx| fut.poll_head(cx)).await?;
+            Ok(BodyStream::new(fut, response))
+        }
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    fn into_http_future(mut self) -> Result<HttpFuture, HttpError> {
+        let has_cookie = self.has_header("cookie");
+        if let Some(jar) = self.client.cookies.as_ref().filter(|_| !has_cookie) {
+            let tls = self.client.server_name.is_some();
+            if let Some(cookie) = jar.header(&self.client.host, &self.path, tls) {
+                self.headers.push(("Cookie".to_string(), cookie));
+            }
+        }
+        // Only a body compressed because we asked for it is inflated
+        #[cfg(feature = "gzip")]
+        let decompress = self.client.auto_decompress && !self.has_header("accept-encoding");
+        #[cfg(feature = "gzip")]
+        if decompress {
+            let accept = ("Accept-Encoding".to_string(), "gzip, deflate".to_string());
+            self.headers.push(accept);
+        }
+
+        let request = build_request(
+            &self.method,
+            &self.client.host,
+            &self.path,
+            &self.headers,
+            self.body.as_deref(),
+            self.client.pool.is_some(),
+        )?;
+        let mut fut = HttpFuture::new(self.client, request, self.timeout);
+        fut.max_response_bytes = self.max_response_bytes;
+        fut.path = self.path;
+        fut.head_request = self.method.eq_ignore_ascii_case("HEAD");
+        #[cfg(feature = "gzip")]
+        {
+            fut.decompress = decompress;
+        }
+        Ok(fut)
     }
 }
 
-struct HttpGetFuture {
-    stream: Option<mio::net::TcpStream>,
-    buffer: Vec<u8>,
+/// Drives a single request/response exchange. The request is formatted up
+/// front, so every HTTP verb shares the same connect, register and read flow.
+struct HttpFuture {
+    // Looks up the address of `client` on the first poll
+    resolve: ResolveFuture,
+    // Set once the address is resolved, until the connection is established
+    connector: Option<Connector>,
+    stream: Option<Connection>,
+    // Status line and headers, including the blank line ending them
+    head: Vec<u8>,
+    // Decoded body bytes that haven't been handed out yet
+    body: Vec<u8>,
+    client: HttpClient,
+    request: Vec<u8>,
+    // Path the request is for, which cookies set by the response apply to
     path: String,
+    // Set for a `HEAD` request, whose response never has a body
+    head_request: bool,
+    // How much of `request` has been written so far
+    written: usize,
     id: usize,
+    framing: Framing,
+    // Set if the request asked for a compressed body
+    #[cfg(feature = "gzip")]
+    decompress: bool,
+    // Set along with `framing` if the body is compressed
+    #[cfg(feature = "gzip")]
+    decoder: Option<encoding::ContentDecoder>,
+    // Set once the whole body has been received
+    complete: bool,
+    timeout: Option<Duration>,
+    // Set on the first poll from `timeout`.
+    deadline: Option<Instant>,
+    // Most bytes `head` and `body` may hold together
+    max_response_bytes: usize,
+    // Set on the first poll from `client.connect_timeout`, unless a pooled
+    // connection is reused
+    connect_deadline: Option<Instant>,
+    // Set on the first poll, once the deadlines are armed
+    started: bool,
+    // Set while `stream` is a connection taken from the pool
+    reused: bool,
 }
 
-impl HttpGetFuture {
-    fn new(path: String) -> Self {
+/// How the end of the response body is detected.
+enum Framing {
+    /// The head hasn't been fully received yet.
+    Unknown,
+    /// `Content-Length` was sent. Holds the number of body bytes still missing.
+    Length(usize),
+    /// `Transfer-Encoding: chunked`. The body is decoded as it arrives.
+    Chunked(ChunkedDecoder),
+    /// Neither was sent, so the body ends when the server closes the connection.
+    UntilClose,
+}
+
+impl HttpFuture {
+    fn new(client: HttpClient, request: Vec<u8>, timeout: Option<Duration>) -> Self {
         let id = reactor().next_id();
         Self {
+            resolve: ResolveFuture::new(&client.addr),
+            connector: None,
             stream: None,
-            buffer: vec![],
-            path: path.to_string(),
+            head: vec![],
+            body: vec![],
+            client,
+            request,
+            path: String::new(),
+            head_request: false,
+            written: 0,
             id,
+            framing: Framing::Unknown,
+            #[cfg(feature = "gzip")]
+            decompress: false,
+            #[cfg(feature = "gzip")]
+            decoder: None,
+            complete: false,
+            timeout,
+            deadline: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            connect_deadline: None,
+            started: false,
+            reused: false,
         }
     }
 
-    fn write_request(&mut self) {
-        let stream = std::net::TcpStream::connect("127.0.0.1:8080").unwrap();
-        stream.set_nonblocking(true).unwrap();
-        let mut stream = mio::net::TcpStream::from_std(stream);
-        stream.write_all(get_req(&self.path).as_bytes()).unwrap();
-        self.stream = Some(stream);
+    /// Releases everything held in the reactor for this request and closes
+    /// the connection. Whatever was buffered so far is discarded.
+    fn finish(&mut self) {
+        if let Some(mut connector) = self.connector.take() {
+            connector.cancel();
+        }
+        if let Some(mut stream) = self.stream.take() {
+            // Nothing to do if this fails, the socket is closed on drop anyway
+            let _ = runtime::reactor().deregister(stream.socket(), self.id);
+        }
+        if self.deadline.is_some() || self.connect_deadline.is_some() {
+            runtime::reactor().clear_timeout(self.id);
+        }
+        self.head = vec![];
+        self.body = vec![];
+    }
+
+    /// Takes an idle connection to the server from the client's pool.
+    fn checkout(&self) -> Option<Connection> {
+        let pool = self.client.pool.as_ref()?;
+        pool.checkout(&self.client.addr, self.client.server_name.as_deref())
     }
-}
 
-impl Future for HttpGetFuture {
-    type Output = String;
+    /// Returns the connection to the client's pool instead of closing it,
+    /// provided the response ended where its framing said it would and the
+    /// server didn't ask for the connection to be closed. Must be called
+    /// once the response is complete, before `finish`.
+    fn release_connection(&mut self) {
+        let Some(pool) = &self.client.pool else {
+            return;
+        };
+        if !matches!(self.framing, Framing::Length(0) | Framing::Chunked(_)) {
+            return;
+        }
+        let close = response::find_header(&self.head, "connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+        if close {
+            return;
+        }
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Some(mut stream) = self.stream.take() {
+            if runtime::reactor().deregister(stream.socket(), self.id).is_ok() {
+                pool.checkin(&self.client.addr, self.client.server_name.as_deref(), stream);
+            }
+        }
+    }
+
+    /// Adds freshly read bytes to the response and returns `true` once it is
+    /// complete. Bytes go into `head` until the blank line ending it is found,
+    /// after which the headers decide how the rest is framed. A chunked body
+    /// is decoded on the fly so `body` only ever holds the plain body.
+    /// Responses without `Content-Length` or chunking are only complete once
+    /// the server closes the connection, which is handled by `poll_progress`.
+    fn receive(&mut self, data: &[u8]) -> Result<bool, HttpError> {
+        let complete = self.buffer(data)?;
+        // A size or trailer line the server never ends is buffered as well
+        let pending = match &self.framing {
+            Framing::Chunked(decoder) => decoder.pending_len(),
+            _ => 0,
+        };
+        if self.head.len() + self.body.len() + pending > self.max_response_bytes {
+            return Err(HttpError::ResponseTooLarge(self.max_response_bytes));
+        }
+        Ok(complete)
+    }
+
+    /// Does the work of `receive`, before the size limit is checked.
+    fn buffer(&mut self, data: &[u8]) -> Result<bool, HttpError> {
+        let data = match self.framing {
+            Framing::Unknown => {
+                self.head.extend_from_slice(data);
+                let Some(start) = response::body_offset(&self.head) else {
+                    return Ok(false);
+                };
+                let rest = self.head.split_off(start);
+                self.framing = framing(&self.head, self.head_request);
+                if let Some(jar) = &self.client.cookies {
+                    for set_cookie in response::find_headers(&self.head, "set-cookie") {
+                        jar.store(&self.client.host, &self.path, set_cookie);
+                    }
+                }
+                // There's nothing to inflate without a body, even though the
+                // headers may say how one would have been encoded
+                #[cfg(feature = "gzip")]
+                if self.decompress && !bodiless(&self.head, self.head_request) {
+                    self.decoder = encoding::ContentDecoder::from_head(&self.head)?;
+                }
+                rest
+            }
+            _ => data.to_vec(),
+        };
+
+        let mut decoded = vec![];
+        let complete = match &mut self.framing {
+            Framing::Length(remaining) => {
+                // A keep-alive server won't close the connection, so stop as
+                // soon as the whole body has arrived.
+                let n = data.len().min(*remaining);
+                decoded.extend_from_slice(&data[..n]);
+                *remaining -= n;
+                *remaining == 0
+            }
+            Framing::Chunked(decoder) => decoder.decode(&data, &mut decoded)?,
+            Framing::UntilClose => {
+                decoded = data;
+                false
+            }
+            Framing::Unknown => unreachable!("framing is known once the head is complete"),
+        };
+
+        self.push_body(&decoded)?;
+        if complete {
+            self.end_body()?;
+        }
+        Ok(complete)
+    }
+
+    /// Adds body bytes, with any chunking already decoded, to `body`. A
+    /// compressed body is inflated on the way.
+    fn push_body(&mut self, data: &[u8]) -> Result<(), HttpError> {
+        #[cfg(feature = "gzip")]
+        if let Some(decoder) = &mut self.decoder {
+            return decoder.decode(data, &mut self.body);
+        }
+        self.body.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Called once the whole body has been received, to flush out the end
+    /// of a compressed body.
+    fn end_body(&mut self) -> Result<(), HttpError> {
+        #[cfg(feature = "gzip")]
+        if let Some(decoder) = &mut self.decoder {
+            decoder.finish(&mut self.body)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves the address and connects to it without blocking, or takes a
+    /// pooled connection. Resolves to `Ok(())` once connected, with the
+    /// socket registered for writing the request.
+    fn poll_connect(&mut self, cx: &mut Context) -> Poll<Result<(), HttpError>> {
         let id = self.id;
-        if self.stream.is_none() {
-            println!("FIRST POLL - START OPERATION");
-            self.write_request();
-            let stream = (&mut self).stream.as_mut().unwrap();
+        if !self.started {
+            // Both deadlines count from here, so a slow lookup counts too
+            self.started = true;
+            let now = Instant::now();
+            if let Some(timeout) = self.timeout {
+                self.deadline = Some(now + timeout);
+                runtime::reactor()
+                    .set_timeout(now + timeout, id)
+                    .map_err(HttpError::Io)?;
+            }
+            if let Some(stream) = self.checkout() {
+                log::debug!("FIRST POLL - REUSING CONNECTION");
+                self.reused = true;
+                let stream = self.stream.insert(stream);
+                let interest = stream.interest() | Interest::WRITABLE;
+                runtime::reactor()
+                    .register(stream.socket(), interest, id)
+                    .map_err(HttpError::Io)?;
+                return Poll::Ready(Ok(()));
+            }
+            if let Some(timeout) = self.client.connect_timeout {
+                self.connect_deadline = Some(now + timeout);
+                runtime::reactor()
+                    .set_timeout(now + timeout, id)
+                    .map_err(HttpError::Io)?;
+            }
+        }
 
-            runtime::reactor().register(stream, Interest::READABLE, id);
-            runtime::reactor().set_waker(cx, self.id);
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Poll::Ready(Err(HttpError::Timeout));
+        }
+        if self.connect_deadline.is_some_and(|d| Instant::now() >= d) {
+            let e = io::Error::new(ErrorKind::TimedOut, "connect timed out");
+            return Poll::Ready(Err(HttpError::Connect(e)));
+        }
+
+        if self.connector.is_none() {
+            // The deadlines wake the task through the reactor, the lookup
+            // through the `Waker` it was polled with
+            runtime::reactor().set_waker(cx, id);
+            let addrs = match Pin::new(&mut self.resolve).poll(cx) {
+                Poll::Ready(Ok(addrs)) => addrs,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(HttpError::Connect(e))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            log::debug!("FIRST POLL - START OPERATION");
+            self.connector = Some(Connector::new(addrs, id));
+        }
+
+        let socket = match self.connector.as_mut().unwrap().poll_connect(cx) {
+            Poll::Ready(Ok(socket)) => socket,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(HttpError::Connect(e))),
+            Poll::Pending => return Poll::Pending,
+        };
+        self.connector = None;
+
+        // Keep the connection in `stream` before registering so `finish`
+        // deregisters the socket if anything after this fails.
+        let server_name = self.client.server_name.clone();
+        let stream = self
+            .stream
+            .insert(Connection::new(socket, server_name.as_deref())?);
+        let interest = stream.interest() | Interest::WRITABLE;
+        runtime::reactor()
+            .reregister(stream.socket(), interest, id)
+            .map_err(HttpError::Io)?;
+
+        if self.connect_deadline.take().is_some() {
+            runtime::reactor().clear_timeout(id);
+            if let Some(deadline) = self.deadline {
+                runtime::reactor()
+                    .set_timeout(deadline, id)
+                    .map_err(HttpError::Io)?;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes as much of the request as the socket accepts, resolving once
+    /// all of it has been written. Only then is the socket reregistered to
+    /// watch for the response alone.
+    fn poll_write(&mut self, cx: &mut Context) -> Poll<Result<(), HttpError>> {
+        let stream = self.stream.as_mut().unwrap();
+        while self.written < self.request.len() {
+            match stream.write(&self.request[self.written..]) {
+                Ok(0) => {
+                    let e = io::Error::new(ErrorKind::WriteZero, "failed to write request");
+                    return Poll::Ready(Err(HttpError::Io(e)));
+                }
+                Ok(n) => self.written += n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    // The socket buffer is full, wait for it to drain
+                    if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                        return Poll::Ready(Err(HttpError::Timeout));
+                    }
+                    if let Err(e) = runtime::reactor().check_running() {
+                        return Poll::Ready(Err(HttpError::Io(e)));
+                    }
+                    runtime::reactor().set_waker(cx, self.id);
+                    return Poll::Pending;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Poll::Ready(Err(HttpError::Io(e))),
+            }
+        }
+
+        let interest = stream.interest();
+        runtime::reactor()
+            .reregister(stream.socket(), interest, self.id)
+            .map_err(HttpError::Io)?;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Whether the connection came from the pool and the server turned out
+    /// to have closed it, with `error` or by just closing it, before any of
+    /// the response arrived. That's the server giving up on an idle
+    /// connection just as it was reused, not a problem with the request.
+    fn closed_while_idle(&self, error: Option<&io::Error>) -> bool {
+        let closed = error.is_none_or(|e| {
+            matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            )
+        });
+        self.reused && self.head.is_empty() && closed
+    }
+
+    /// Drops a pooled connection the server closed and sends the request
+    /// again on a fresh one. It's only done once, since a fresh connection
+    /// isn't `reused`.
+    fn reconnect(&mut self, cx: &mut Context) -> Poll<Result<bool, HttpError>> {
+        log::debug!("POOLED CONNECTION CLOSED - RECONNECTING");
+        if let Some(mut stream) = self.stream.take() {
+            let _ = runtime::reactor().deregister(stream.socket(), self.id);
+        }
+        self.reused = false;
+        self.written = 0;
+        if let Some(timeout) = self.client.connect_timeout {
+            let deadline = Instant::now() + timeout;
+            self.connect_deadline = Some(deadline);
+            if let Err(e) = runtime::reactor().set_timeout(deadline, self.id) {
+                return Poll::Ready(Err(HttpError::Io(e)));
+            }
+        }
+        self.poll_progress(cx)
+    }
+
+    /// Makes progress on the exchange: connects and writes the request on
+    /// the first calls, then reads from the connection. Returns `Ready(Ok(true))` once the response
+    /// is complete and `Ready(Ok(false))` whenever new data was received.
+    fn poll_progress(&mut self, cx: &mut Context) -> Poll<Result<bool, HttpError>> {
+        if self.stream.is_none() {
+            match self.poll_connect(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        if self.written < self.request.len() {
+            match self.poll_write(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(HttpError::Io(e))) if self.closed_while_idle(Some(&e)) => {
+                    return self.reconnect(cx);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
         }
 
         let mut buff = vec![0u8; 4096];
         loop {
             match self.stream.as_mut().unwrap().read(&mut buff) {
+                Ok(0) if self.closed_while_idle(None) => return self.reconnect(cx),
+                Err(e) if self.closed_while_idle(Some(&e)) => return self.reconnect(cx),
                 Ok(0) => {
-                    let s = String::from_utf8_lossy(&self.buffer).to_string();
-                    runtime::reactor()
-                        .deregister(self.stream.as_mut().unwrap(), id);
-                    break Poll::Ready(s);
-                }
-                Ok(n) => {
-                    self.buffer.extend(&buff[0..n]);
-                    continue;
+                    let missing = match self.framing {
+                        Framing::Chunked(_) => Some("end of chunked body".to_string()),
+                        Framing::Length(remaining) if remaining > 0 => {
+                            Some(format!("{remaining} more body bytes"))
+                        }
+                        _ => None,
+                    };
+                    if let Some(missing) = missing {
+                        return Poll::Ready(Err(HttpError::MalformedResponse(format!(
+                            "connection closed before {missing}"
+                        ))));
+                    }
+                    return Poll::Ready(self.end_body().map(|()| true));
                 }
+                Ok(n) => return Poll::Ready(self.receive(&buff[0..n])),
                 Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    // We're woken both by readiness events and when the
+                    // deadline passes, so check which one it was.
+                    if self.deadline.is_some_and(|d| Instant::now() >= d) {
+                        return Poll::Ready(Err(HttpError::Timeout));
+                    }
+                    if let Err(e) = runtime::reactor().check_running() {
+                        return Poll::Ready(Err(HttpError::Io(e)));
+                    }
+
                     // https://doc.rust-lang.org/stable/std/future/trait.Future.html#tymethod.poll
                     // The `Waker` from the most recent call is expected to be scheduled to wake up.
                     // Meaning every time a `WouldBlock` error is received, the most recent `Waker`
                     // must be stored.
                     runtime::reactor().set_waker(cx, self.id);
-                    break Poll::Pending;
+                    return Poll::Pending;
                 }
                 Err(e) if e.kind() == ErrorKind::Interrupted => {
                     continue;
                 }
-                Err(e) => panic!("{e:?}"),
+                // Only over TLS, when the connection closed without
+                // close_notify. Whatever framing the body has, it may have
+                // been cut off by an attacker.
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                    return Poll::Ready(Err(HttpError::MalformedResponse(
+                        "connection closed without TLS close_notify".to_string(),
+                    )));
+                }
+                Err(e) => return Poll::Ready(Err(HttpError::Io(e))),
+            }
+        }
+    }
+
+    /// Reads until the whole head has arrived and parses it. The returned
+    /// `Response` has an empty body; the body is read with `poll_chunk`.
+    fn poll_head(&mut self, cx: &mut Context) -> Poll<Result<Response, HttpError>> {
+        while let Framing::Unknown = self.framing {
+            match self.poll_progress(cx) {
+                // The whole response might arrive together with the head
+                Poll::Ready(Ok(done)) => {
+                    self.complete = done;
+                    if done {
+                        break;
+                    }
+                }
+                Poll::Ready(Err(e)) => {
+                    self.finish();
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => return Poll::Pending,
             }
         }
+        Poll::Ready(Response::parse(&self.head, vec![]))
+    }
+
+    /// Hands out the body bytes received since the last call, reading more
+    /// if there are none. Resolves to `None` once the body is complete, at
+    /// which point the stream is deregistered.
+    fn poll_chunk(&mut self, cx: &mut Context) -> Poll<Result<Option<Vec<u8>>, HttpError>> {
+        loop {
+            if !self.body.is_empty() {
+                return Poll::Ready(Ok(Some(std::mem::take(&mut self.body))));
+            }
+            if self.complete {
+                self.release_connection();
+                self.finish();
+                return Poll::Ready(Ok(None));
+            }
+
+            match self.poll_progress(cx) {
+                Poll::Ready(Ok(done)) => self.complete = done,
+                Poll::Ready(Err(e)) => {
+                    self.finish();
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Whether a response can't have a body, whatever its headers say: the one
+/// to a `HEAD` request, as well as `1xx`, `204` and `304` responses.
+fn bodiless(head: &[u8], head_request: bool) -> bool {
+    let status = response::find_status(head).unwrap_or_default();
+    head_request || (100..200).contains(&status) || status == 204 || status == 304
+}
+
+/// Decides how the body is framed based on the response head, with no body
+/// at all for a `bodiless` response.
+fn framing(head: &[u8], head_request: bool) -> Framing {
+    if bodiless(head, head_request) {
+        return Framing::Length(0);
+    }
+
+    let chunked = response::find_header(head, "transfer-encoding")
+        .is_some_and(|te| te.to_ascii_lowercase().ends_with("chunked"));
+    if chunked {
+        return Framing::Chunked(ChunkedDecoder::new());
+    }
+
+    match response::find_header(head, "content-length").and_then(|len| len.parse().ok()) {
+        Some(len) => Framing::Length(len),
+        None => Framing::UntilClose,
+    }
+}
+
+impl Future for HttpFuture {
+    type Output = Result<Response, HttpError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let result = loop {
+            match self.poll_progress(cx) {
+                Poll::Ready(Ok(true)) => {
+                    let body = std::mem::take(&mut self.body);
+                    let result = Response::parse(&self.head, body);
+                    if result.is_ok() {
+                        self.release_connection();
+                    }
+                    break result;
+                }
+                Poll::Ready(Ok(false)) => continue,
+                Poll::Ready(Err(e)) => break Err(e),
+                Poll::Pending => return Poll::Pending,
+            }
+        };
+
+        self.finish();
+        Poll::Ready(result)
+    }
+}
+
+impl Drop for HttpFuture {
+    /// A request dropped before completing, e.g. because it lost a `race`
+    /// against a timeout or its task was aborted, must not leave its socket
+    /// registered or its `Waker` behind for the reactor to wake on a dead
+    /// id. The socket is deregistered and closed right away, whether still
+    /// connecting, writing or reading, and the id is released either way.
+    fn drop(&mut self) {
+        self.finish();
+        runtime::reactor().release_id(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let base = Duration::from_millis(100);
+        assert_eq!(backoff(base, 0), base);
+        assert_eq!(backoff(base, 3), Duration::from_millis(800));
+        assert_eq!(backoff(base, 20), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        assert_eq!(backoff(Duration::from_millis(1), u32::MAX), MAX_BACKOFF);
+        assert_eq!(backoff(Duration::MAX, 1), MAX_BACKOFF);
     }
 }
@@ -0,0 +1,27 @@
+use super::{HttpError, HttpFuture, Response};
+use std::future::{self, Future};
+
+/// A response whose body is read incrementally. Each call to `next_chunk`
+/// yields the bytes that arrived since the previous one, so large bodies
+/// never have to be held in memory all at once.
+pub struct BodyStream {
+    inner: HttpFuture,
+    response: Response,
+}
+
+impl BodyStream {
+    pub(super) fn new(inner: HttpFuture, response: Response) -> Self {
+        Self { inner, response }
+    }
+
+    /// Status and headers of the response. Its `body` is always empty.
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Resolves to the next piece of the body, or `None` once all of it has
+    /// been received. A chunked body is handed out already decoded.
+    pub fn next_chunk(&mut self) -> impl Future<Output = Result<Option<Vec<u8>>, HttpError>> + '_ {
+        future::poll_fn(|cx| self.inner.poll_chunk(cx))
+    }
+}
@@ -0,0 +1,111 @@
+use super::HttpError;
+
+/// Where the decoder is within the chunked body.
+enum State {
+    /// Waiting for a line with the hex size of the next chunk.
+    Size,
+    /// Reading the data of the current chunk; holds the bytes still missing.
+    Data(usize),
+    /// Expecting the CRLF that follows the data of every chunk.
+    DataEnd,
+    /// After the last (zero sized) chunk. Skips trailer lines until the
+    /// closing empty line.
+    Trailer,
+    Done,
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body. Data arrives in whatever
+/// fragments the socket hands us, so bytes that can't be decoded yet (e.g.
+/// half a size line) are kept in `pending` until the next call to `decode`.
+pub(super) struct ChunkedDecoder {
+    state: State,
+    pending: Vec<u8>,
+}
+
+impl ChunkedDecoder {
+    pub(super) fn new() -> Self {
+        Self {
+            state: State::Size,
+            pending: vec![],
+        }
+    }
+
+    /// Decodes as much of `data` as possible, appending the chunk contents to
+    /// `out`. Returns `true` once the terminating chunk and trailer have been
+    /// received.
+    pub(super) fn decode(&mut self, data: &[u8], out: &mut Vec<u8>) -> Result<bool, HttpError> {
+        self.pending.extend_from_slice(data);
+        let mut pos = 0;
+
+        loop {
+            let rest = &self.pending[pos..];
+            match self.state {
+                State::Size => {
+                    let Some(line) = take_line(rest) else { break };
+                    pos += line.len() + 2;
+                    let size = parse_size(line)?;
+                    self.state = if size == 0 {
+                        State::Trailer
+                    } else {
+                        State::Data(size)
+                    };
+                }
+                State::Data(remaining) => {
+                    if rest.is_empty() {
+                        break;
+                    }
+                    let n = remaining.min(rest.len());
+                    out.extend_from_slice(&rest[..n]);
+                    pos += n;
+                    self.state = match remaining - n {
+                        0 => State::DataEnd,
+                        left => State::Data(left),
+                    };
+                }
+                State::DataEnd => {
+                    if rest.len() < 2 {
+                        break;
+                    }
+                    if &rest[..2] != b"\r\n" {
+                        return Err(malformed("missing CRLF after chunk data"));
+                    }
+                    pos += 2;
+                    self.state = State::Size;
+                }
+                State::Trailer => {
+                    let Some(line) = take_line(rest) else { break };
+                    pos += line.len() + 2;
+                    if line.is_empty() {
+                        self.state = State::Done;
+                    }
+                }
+                State::Done => break,
+            }
+        }
+
+        self.pending.drain(..pos);
+        Ok(matches!(self.state, State::Done))
+    }
+
+    /// Number of bytes kept for the next call to `decode`, e.g. a size or
+    /// trailer line that hasn't ended yet.
+    pub(super) fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Returns the bytes up to (not including) the next CRLF, if there is one.
+fn take_line(buf: &[u8]) -> Option<&[u8]> {
+    buf.windows(2).position(|w| w == b"\r\n").map(|i| &buf[..i])
+}
+
+/// Parses a chunk size line, ignoring any chunk extensions after `;`.
+fn parse_size(line: &[u8]) -> Result<usize, HttpError> {
+    let line = std::str::from_utf8(line).map_err(|_| malformed("invalid chunk size"))?;
+    let size = line.split(';').next().unwrap_or_default().trim();
+    usize::from_str_radix(size, 16).map_err(|_| malformed(&format!("invalid chunk size: {size:?}")))
+}
+
+fn malformed(msg: &str) -> HttpError {
+    HttpError::MalformedResponse(msg.to_string())
+}
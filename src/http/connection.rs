@@ -0,0 +1,174 @@
+#[cfg(feature = "tls")]
+use super::tls::TlsStream;
+use super::HttpError;
+use crate::runtime;
+use mio::{net::TcpStream, Interest};
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    net::SocketAddr,
+    task::{Context, Poll},
+};
+
+/// Establishes a TCP connection without blocking, trying `addrs` in order
+/// until one of them accepts. Each attempt starts a non-blocking connect and
+/// registers the socket for `WRITABLE` readiness, which the reactor signals
+/// once the handshake has either completed or failed.
+pub(super) struct Connector {
+    addrs: VecDeque<SocketAddr>,
+    // Socket of the attempt in flight, registered with the reactor under `id`
+    socket: Option<TcpStream>,
+    // Error of the most recent failed attempt, returned if none succeed
+    last_error: Option<io::Error>,
+    id: usize,
+}
+
+impl Connector {
+    pub(super) fn new(addrs: Vec<SocketAddr>, id: usize) -> Self {
+        Self {
+            addrs: addrs.into(),
+            socket: None,
+            last_error: None,
+            id,
+        }
+    }
+
+    /// Resolves to the connected socket, still registered with the reactor
+    /// for write readiness under this connector's `id`.
+    pub(super) fn poll_connect(&mut self, cx: &mut Context) -> Poll<io::Result<TcpStream>> {
+        loop {
+            let Some(socket) = self.socket.as_mut() else {
+                let Some(addr) = self.addrs.pop_front() else {
+                    let e = self.last_error.take().unwrap_or_else(|| {
+                        io::Error::new(ErrorKind::InvalidInput, "no addresses to connect to")
+                    });
+                    return Poll::Ready(Err(e));
+                };
+
+                match TcpStream::connect(addr) {
+                    Ok(mut socket) => {
+                        let interest = Interest::WRITABLE;
+                        match runtime::reactor().register(&mut socket, interest, self.id) {
+                            Ok(()) => {
+                                runtime::reactor().set_waker(cx, self.id);
+                                self.socket = Some(socket);
+                            }
+                            Err(e) => self.last_error = Some(e),
+                        }
+                    }
+                    Err(e) => self.last_error = Some(e),
+                }
+                continue;
+            };
+
+            // Only a writable event means the handshake is done, anything
+            // else woke us for another reason, e.g. the connect timeout
+            if !runtime::reactor().take_readiness(self.id).writable {
+                runtime::reactor().check_running()?;
+                runtime::reactor().set_waker(cx, self.id);
+                return Poll::Pending;
+            }
+
+            // A connect that failed reports its error through `SO_ERROR`
+            match socket.take_error() {
+                Ok(None) => {}
+                Ok(Some(e)) | Err(e) => {
+                    self.fail(e);
+                    continue;
+                }
+            }
+
+            match socket.peer_addr() {
+                Ok(_) => return Poll::Ready(Ok(self.socket.take().unwrap())),
+                // Woken before the handshake is done
+                Err(e) if e.kind() == ErrorKind::NotConnected => {
+                    runtime::reactor().check_running()?;
+                    runtime::reactor().set_waker(cx, self.id);
+                    return Poll::Pending;
+                }
+                Err(e) => self.fail(e),
+            }
+        }
+    }
+
+    /// Abandons the attempt in flight, if any.
+    pub(super) fn cancel(&mut self) {
+        if let Some(mut socket) = self.socket.take() {
+            let _ = runtime::reactor().deregister(&mut socket, self.id);
+        }
+    }
+
+    fn fail(&mut self, e: io::Error) {
+        self.cancel();
+        self.last_error = Some(e);
+    }
+}
+
+/// The transport a request is sent over: plain TCP, or TLS on top of it.
+pub(super) enum Connection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<TlsStream>),
+}
+
+impl Connection {
+    /// Wraps a connected socket. With a `server_name`, a TLS session for that
+    /// name is set up on top of it. The handshake itself is driven by `read`.
+    pub(super) fn new(socket: TcpStream, server_name: Option<&str>) -> Result<Self, HttpError> {
+        match server_name {
+            None => Ok(Connection::Plain(socket)),
+            #[cfg(feature = "tls")]
+            Some(name) => Ok(Connection::Tls(Box::new(TlsStream::new(socket, name)?))),
+            #[cfg(not(feature = "tls"))]
+            Some(_) => unreachable!("TLS requested without the `tls` feature"),
+        }
+    }
+
+    /// The underlying socket, used for registering with the reactor.
+    pub(super) fn socket(&mut self) -> &mut TcpStream {
+        match self {
+            Connection::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Connection::Tls(tls) => tls.socket(),
+        }
+    }
+
+    /// Readiness the reactor should watch for once the request is written. A TLS
+    /// handshake needs to write as well as read, so it's woken on both.
+    pub(super) fn interest(&self) -> Interest {
+        match self {
+            Connection::Plain(_) => Interest::READABLE,
+            #[cfg(feature = "tls")]
+            Connection::Tls(_) => Interest::READABLE | Interest::WRITABLE,
+        }
+    }
+
+    /// Whether an idle connection can still be used. Anything but
+    /// `WouldBlock` on the socket means it can't: either the server closed
+    /// it or it sent something that wasn't asked for.
+    pub(super) fn is_idle(&mut self) -> bool {
+        let mut buf = [0u8; 1];
+        matches!(self.socket().peek(&mut buf), Err(e) if e.kind() == ErrorKind::WouldBlock)
+    }
+
+    /// Writes as much of `buf` as the socket accepts without blocking. With
+    /// TLS all of it is accepted, it's buffered until the handshake is done
+    /// and flushed by `read`.
+    pub(super) fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(tls) => tls.send(buf).map(|()| buf.len()),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(tls) => tls.read(buf),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Cookies set by the servers an `HttpClient` talks to. Once a client has a
+/// jar, every `Set-Cookie` header it receives is recorded, and the cookies
+/// matching a request's host and path are sent along with it in a `Cookie`
+/// header. Cookies are dropped once their `Expires` or `Max-Age` passes.
+///
+/// Clones share the same cookies, so one jar can be handed to several
+/// clients and still be inspected afterwards.
+#[derive(Clone, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+struct Cookie {
+    name: String,
+    value: String,
+    // Lowercased, without a port
+    domain: String,
+    // Without a `Domain` attribute only the host that set it gets it back
+    host_only: bool,
+    path: String,
+    // `None` for a session cookie, kept for as long as the jar is
+    expires: Option<SystemTime>,
+    secure: bool,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks up the value of the cookie called `name`, for any host and
+    /// path, unless it has expired.
+    pub fn get(&self, name: &str) -> Option<String> {
+        let mut cookies = self.lock();
+        evict(&mut cookies);
+        cookies
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.value.clone())
+    }
+
+    /// Forgets every cookie.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Records the cookie from a `Set-Cookie` header received in response to
+    /// a request for `path` on `host`. A cookie with the same name, domain
+    /// and path is replaced, which is also how servers delete one: by setting
+    /// it again with an expiry in the past. Cookies for a domain that `host`
+    /// doesn't belong to are ignored.
+    pub(super) fn store(&self, host: &str, path: &str, set_cookie: &str) {
+        let host = strip_port(host);
+        let mut attributes = set_cookie.split(';');
+        let Some((name, value)) = attributes.next().and_then(|pair| pair.split_once('=')) else {
+            return;
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(path),
+            expires: None,
+            secure: false,
+        };
+        let mut max_age = None;
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "expires" => cookie.expires = cookie.expires.or(parse_date(value)),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain_match(&host, &domain) {
+                        return;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                _ => {}
+            }
+        }
+        // `Max-Age` wins over `Expires`, and zero or less expires right away.
+        // One too far out to represent never expires.
+        if let Some(secs) = max_age {
+            let secs = u64::try_from(secs).unwrap_or(0);
+            cookie.expires = SystemTime::now().checked_add(Duration::from_secs(secs));
+        }
+
+        let mut cookies = self.lock();
+        cookies.retain(|c| {
+            c.name != cookie.name || c.domain != cookie.domain || c.path != cookie.path
+        });
+        cookies.push(cookie);
+        evict(&mut cookies);
+    }
+
+    /// Builds the value of the `Cookie` header for a request for `path` on
+    /// `host`, or `None` if no cookie matches. `Secure` cookies are only sent
+    /// over TLS. Cookies with longer paths come first.
+    pub(super) fn header(&self, host: &str, path: &str, tls: bool) -> Option<String> {
+        let host = strip_port(host);
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let mut cookies = self.lock();
+        evict(&mut cookies);
+
+        let mut matching: Vec<&Cookie> = cookies
+            .iter()
+            .filter(|c| {
+                if c.host_only {
+                    host == c.domain
+                } else {
+                    domain_match(&host, &c.domain)
+                }
+            })
+            .filter(|c| path_match(path, &c.path))
+            .filter(|c| tls || !c.secure)
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Cookie>> {
+        // A panic while holding the lock can't leave the cookies half updated
+        self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Drops every cookie that has expired.
+fn evict(cookies: &mut Vec<Cookie>) {
+    let now = SystemTime::now();
+    cookies.retain(|c| c.expires.is_none_or(|expires| expires > now));
+}
+
+/// Lowercases `host` and removes the port from it, if there is one.
+fn strip_port(host: &str) -> String {
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    host.to_ascii_lowercase()
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.ends_with('.'))
+}
+
+/// Whether a cookie for `cookie_path` is sent with a request for `path`:
+/// the same path, or one below it.
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    match path.strip_prefix(cookie_path) {
+        Some(rest) => rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Path of a cookie set without a `Path` attribute: the "directory" of the
+/// request path, e.g. `/account` for `/account/login`.
+fn default_path(path: &str) -> String {
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    match path.rfind('/') {
+        Some(i) if i > 0 && path.starts_with('/') => path[..i].to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+/// Parses the date of an `Expires` attribute, e.g.
+/// `Wed, 21 Oct 2015 07:28:00 GMT`. Servers send it in a few different
+/// formats, so like browsers this only looks for the time, day, month and
+/// year among its parts, in whatever order. Gives `None` if any is missing
+/// or out of range, which makes it a session cookie.
+fn parse_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+
+    let tokens = value.split(|c: char| !c.is_ascii_alphanumeric() && c != ':');
+    for token in tokens.filter(|t| !t.is_empty()) {
+        let digits = token.bytes().all(|b| b.is_ascii_digit());
+        if time.is_none() && token.contains(':') {
+            let mut parts = token.split(':').map(|p| p.parse::<u64>().ok());
+            if let (Some(Some(h)), Some(Some(m)), Some(Some(s))) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                if h > 23 || m > 59 || s > 59 {
+                    return None;
+                }
+                time = Some(h * 3600 + m * 60 + s);
+            }
+        } else if day.is_none() && digits && token.len() <= 2 {
+            day = Some(token.parse::<u32>().ok().filter(|d| (1..=31).contains(d))?);
+        } else if month.is_none() && !digits && token.len() >= 3 {
+            let prefix = token[..3].to_ascii_lowercase();
+            if let Some(i) = MONTHS.iter().position(|m| *m == prefix) {
+                month = Some(i as u32 + 1);
+            }
+        } else if year.is_none() && digits && (2..=4).contains(&token.len()) {
+            year = token.parse::<i64>().ok().map(|y| match y {
+                0..=69 => y + 2000,
+                70..=99 => y + 1900,
+                _ => y,
+            });
+        }
+    }
+
+    let secs = days_from_civil(year?, month?, day?) * 86400 + time? as i64;
+    // Anything before 1970 has expired just the same
+    let secs = u64::try_from(secs).unwrap_or(0);
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Counting from March puts the leap day at the end of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_expires_date() {
+        let date = parse_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(date, UNIX_EPOCH + Duration::from_secs(1_445_412_480));
+    }
+
+    #[test]
+    fn rejects_out_of_range_dates() {
+        for value in [
+            "Wed, 21 Oct 2015 24:00:00 GMT",
+            "Wed, 21 Oct 2015 07:60:00 GMT",
+            "Wed, 21 Oct 2015 07:28:60 GMT",
+            "Wed, 0 Oct 2015 07:28:00 GMT",
+            "Wed, 32 Oct 2015 07:28:00 GMT",
+            "Wed, 21 Oct 2015 99999999999999999:00:00 GMT",
+        ] {
+            assert_eq!(parse_date(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn huge_max_age_never_expires() {
+        let jar = CookieJar::new();
+        jar.store("example.com", "/", "id=1; Max-Age=9223372036854775807");
+        assert_eq!(jar.get("id").as_deref(), Some("1"));
+    }
+}
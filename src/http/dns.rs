@@ -0,0 +1,55 @@
+use crate::runtime::{self, JoinHandle};
+use std::{
+    future::Future,
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Resolves a `host:port` string to its socket addresses. `ToSocketAddrs`
+/// blocks while the lookup is in flight, so for anything that isn't already
+/// a literal address it runs through `spawn_blocking`, whose `JoinHandle`
+/// wakes the task once the result is ready. Addresses are returned in the
+/// order the resolver gave them.
+pub(super) struct ResolveFuture {
+    addr: String,
+    lookup: Option<JoinHandle<io::Result<Vec<SocketAddr>>>>,
+}
+
+impl ResolveFuture {
+    pub(super) fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            lookup: None,
+        }
+    }
+}
+
+impl Future for ResolveFuture {
+    type Output = io::Result<Vec<SocketAddr>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let lookup = match &mut self.lookup {
+            Some(lookup) => lookup,
+            None => {
+                // No need for a lookup if we were handed an IP address
+                if let Ok(addr) = self.addr.parse() {
+                    return Poll::Ready(Ok(vec![addr]));
+                }
+
+                let addr = self.addr.clone();
+                let lookup =
+                    runtime::spawn_blocking(move || addr.to_socket_addrs().map(Iterator::collect));
+                self.lookup.insert(lookup)
+            }
+        };
+
+        match Pin::new(lookup).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            // Only if the lookup panicked
+            Poll::Ready(Err(e)) => Poll::Ready(Err(io::Error::other(e))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::{response, HttpError};
+use flate2::write::{GzDecoder, ZlibDecoder};
+use std::io::Write;
+
+/// Inflates a body sent with `Content-Encoding: gzip` or `deflate` as it
+/// arrives, after any chunking has been decoded. Both decoders write the
+/// inflated bytes into a `Vec` that's drained on every call, so only what
+/// hasn't been handed out yet is kept.
+pub(super) enum ContentDecoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    /// `deflate` is the zlib format, as the HTTP spec defines it.
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl ContentDecoder {
+    /// Picks the decoder the `Content-Encoding` of a raw head calls for, or
+    /// `None` if the body isn't compressed. Fails for any encoding that's
+    /// not supported, rather than handing out a body that can't be read.
+    pub(super) fn from_head(head: &[u8]) -> Result<Option<Self>, HttpError> {
+        let Some(encoding) = response::find_header(head, "content-encoding") else {
+            return Ok(None);
+        };
+        match encoding.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(ContentDecoder::Gzip(GzDecoder::new(vec![])))),
+            "deflate" => Ok(Some(ContentDecoder::Deflate(ZlibDecoder::new(vec![])))),
+            _ => Err(HttpError::UnsupportedEncoding(encoding.to_string())),
+        }
+    }
+
+    /// Inflates `data`, appending whatever that produced to `out`.
+    pub(super) fn decode(&mut self, data: &[u8], out: &mut Vec<u8>) -> Result<(), HttpError> {
+        match self {
+            ContentDecoder::Gzip(decoder) => decoder.write_all(data),
+            ContentDecoder::Deflate(decoder) => decoder.write_all(data),
+        }
+        .map_err(invalid)?;
+        out.append(self.inflated());
+        Ok(())
+    }
+
+    /// Called once the whole body has arrived. Appends what's left to `out`
+    /// and fails if the compressed stream ended early.
+    pub(super) fn finish(&mut self, out: &mut Vec<u8>) -> Result<(), HttpError> {
+        match self {
+            ContentDecoder::Gzip(decoder) => decoder.try_finish(),
+            ContentDecoder::Deflate(decoder) => decoder.try_finish(),
+        }
+        .map_err(invalid)?;
+        out.append(self.inflated());
+        Ok(())
+    }
+
+    fn inflated(&mut self) -> &mut Vec<u8> {
+        match self {
+            ContentDecoder::Gzip(decoder) => decoder.get_mut(),
+            ContentDecoder::Deflate(decoder) => decoder.get_mut(),
+        }
+    }
+}
+
+fn invalid(e: std::io::Error) -> HttpError {
+    HttpError::MalformedResponse(format!("invalid compressed body: {e}"))
+}
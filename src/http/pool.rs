@@ -0,0 +1,71 @@
+use super::connection::Connection;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Where a connection goes: the address and, for TLS, the server name the
+/// session was set up for.
+type Key = (String, Option<String>);
+
+/// Connections per key, along with when they became idle. The most recently
+/// returned one is last.
+type Idle = HashMap<Key, Vec<(Connection, Instant)>>;
+
+/// Idle connections kept open by an `HttpClient` with keep-alive enabled.
+/// Connections in the pool aren't registered with the reactor, nothing
+/// watches them. Expired ones are evicted whenever the pool is used.
+pub(super) struct Pool {
+    idle_timeout: Duration,
+    idle: Mutex<Idle>,
+}
+
+impl Pool {
+    pub(super) fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes the most recently returned connection for `addr` that hasn't
+    /// expired or been closed by the server, if there is one.
+    pub(super) fn checkout(&self, addr: &str, server_name: Option<&str>) -> Option<Connection> {
+        let key = (addr.to_string(), server_name.map(str::to_string));
+        let mut idle = self.lock();
+        self.evict(&mut idle);
+        let connections = idle.get_mut(&key)?;
+        while let Some((mut connection, _)) = connections.pop() {
+            if connection.is_idle() {
+                return Some(connection);
+            }
+        }
+        None
+    }
+
+    /// Keeps `connection` around for the next request to `addr`. It must
+    /// have been deregistered from the reactor already.
+    pub(super) fn checkin(&self, addr: &str, server_name: Option<&str>, connection: Connection) {
+        let key = (addr.to_string(), server_name.map(str::to_string));
+        let mut idle = self.lock();
+        self.evict(&mut idle);
+        idle.entry(key)
+            .or_default()
+            .push((connection, Instant::now()));
+    }
+
+    /// Closes every connection that's been idle for longer than the timeout.
+    fn evict(&self, idle: &mut Idle) {
+        let now = Instant::now();
+        for connections in idle.values_mut() {
+            connections.retain(|(_, since)| now.duration_since(*since) < self.idle_timeout);
+        }
+        idle.retain(|_, connections| !connections.is_empty());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Idle> {
+        // A panic while holding the lock can't leave the map half updated
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt::Write;
+
+/// Builds a percent-encoded query string from key/value pairs, e.g.
+/// `q=hello%20world&page=2`, to be appended to a path after a `?`.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    params: Vec<(String, String)>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parameter. Both `key` and `value` are encoded by `build`, and
+    /// parameters keep the order they were added in.
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn build(&self) -> String {
+        let mut query = String::new();
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            if i > 0 {
+                query.push('&');
+            }
+            encode_into(&mut query, key);
+            query.push('=');
+            encode_into(&mut query, value);
+        }
+        query
+    }
+}
+
+/// Percent-encodes everything except the unreserved characters of RFC 3986
+/// (`A-Z a-z 0-9 - . _ ~`). Non-ASCII characters are encoded byte by byte
+/// from their UTF-8 representation.
+fn encode_into(out: &mut String, s: &str) {
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            _ => write!(out, "%{b:02X}").unwrap(),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::{HttpClient, HttpError};
+
+/// Statuses that carry a `Location` to follow.
+pub(super) fn is_redirect(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Works out where a `Location` header points to, relative to the request
+/// that was made to `path` using `client`. Absolute URLs switch to a client
+/// for the new address; paths keep using the same one.
+pub(super) fn resolve(
+    client: &HttpClient,
+    path: &str,
+    location: &str,
+) -> Result<(HttpClient, String), HttpError> {
+    if let Some(rest) = location.strip_prefix("http://") {
+        let (authority, path) = split_authority(rest);
+        let addr = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{authority}:80")
+        };
+        let client = HttpClient {
+            addr,
+            host: authority.to_string(),
+            server_name: None,
+            ..client.clone()
+        };
+        return Ok((client, path));
+    }
+
+    if let Some(rest) = location.strip_prefix("https://") {
+        return resolve_https(client, rest);
+    }
+
+    if location.starts_with('/') {
+        return Ok((client.clone(), location.to_string()));
+    }
+
+    // Relative to the "directory" of the current path, which a `/` in its
+    // query can't move
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let base = path.rsplit_once('/').map_or("", |(base, _)| base);
+    Ok((client.clone(), format!("{base}/{location}")))
+}
+
+#[cfg(feature = "tls")]
+fn resolve_https(client: &HttpClient, rest: &str) -> Result<(HttpClient, String), HttpError> {
+    let (authority, path) = split_authority(rest);
+    let (server_name, addr) = match authority.split_once(':') {
+        Some((name, _)) => (name, authority.to_string()),
+        None => (authority, format!("{authority}:443")),
+    };
+    let client = HttpClient {
+        addr,
+        host: authority.to_string(),
+        server_name: Some(server_name.to_string()),
+        ..client.clone()
+    };
+    Ok((client, path))
+}
+
+#[cfg(not(feature = "tls"))]
+fn resolve_https(_client: &HttpClient, rest: &str) -> Result<(HttpClient, String), HttpError> {
+    Err(HttpError::MalformedResponse(format!(
+        "can't follow redirect to https://{rest} without the `tls` feature"
+    )))
+}
+
+/// Splits `host:port/path?query` into the authority and the path, which
+/// defaults to `/`.
+fn split_authority(rest: &str) -> (&str, String) {
+    match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('?') => (&rest[..i], format!("/{}", &rest[i..])),
+        Some(i) => (&rest[..i], rest[i..].to_string()),
+        None => (rest, "/".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_path(path: &str, location: &str) -> String {
+        let client = HttpClient::new("127.0.0.1:8080");
+        resolve(&client, path, location).unwrap().1
+    }
+
+    #[test]
+    fn relative_location_replaces_last_segment() {
+        assert_eq!(resolve_path("/a/b", "c"), "/a/c");
+        assert_eq!(resolve_path("/a/", "c"), "/a/c");
+        assert_eq!(resolve_path("/", "c?d=1"), "/c?d=1");
+    }
+
+    #[test]
+    fn relative_location_ignores_query_and_fragment() {
+        assert_eq!(resolve_path("/a/b?next=/x", "c"), "/a/c");
+        assert_eq!(resolve_path("/a/b#/x/y", "c"), "/a/c");
+    }
+
+    #[test]
+    fn absolute_path_location_is_kept() {
+        assert_eq!(resolve_path("/a/b?next=/x", "/c"), "/c");
+    }
+}
@@ -0,0 +1,147 @@
+use super::HttpError;
+use std::collections::HashMap;
+
+/// A response split into its status line, headers and body.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// Protocol version from the status line, e.g. `HTTP/1.1`.
+    pub version: String,
+    /// Numeric status code, e.g. `200`.
+    pub status: u16,
+    /// Reason phrase following the status code, e.g. `OK` or `Not Found`.
+    /// Empty if the server didn't send one.
+    pub reason: String,
+    /// Header values keyed by their lowercased name. When a header is sent
+    /// more than once this holds the last value; use `header_all` to get
+    /// every one of them.
+    pub headers: HashMap<String, String>,
+    // Every header in the order it was received, with lowercased names.
+    all_headers: Vec<(String, String)>,
+    /// Everything after the blank line that ends the header block. With the
+    /// `gzip` feature a body sent with `Content-Encoding: gzip` or `deflate`
+    /// is inflated if `HttpClient::auto_decompress` is turned on,
+    /// while the headers are kept as the server sent them.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Parses the raw head of a response (status line and headers, up to and
+    /// including the blank line) and pairs it with the already decoded body.
+    pub(super) fn parse(head: &[u8], body: Vec<u8>) -> Result<Self, HttpError> {
+        let body_start =
+            body_offset(head).ok_or_else(|| malformed("missing blank line after headers"))?;
+        let head = std::str::from_utf8(&head[..body_start - 4])
+            .map_err(|_| malformed("headers are not valid UTF-8"))?;
+
+        let mut lines = head.split("\r\n");
+        let (version, status, reason) = parse_status_line(lines.next().unwrap_or_default())?;
+
+        let mut all_headers = vec![];
+        for line in lines {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| malformed(&format!("invalid header line: {line:?}")))?;
+            all_headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+        let headers = all_headers.iter().cloned().collect();
+
+        Ok(Self {
+            version,
+            status,
+            reason,
+            headers,
+            all_headers,
+            body,
+        })
+    }
+
+    /// Looks up a header value. The name is matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Returns every value of a header that may be sent multiple times, such
+    /// as `Set-Cookie`, in the order they were received.
+    pub fn header_all(&self, name: &str) -> Vec<String> {
+        let name = name.to_ascii_lowercase();
+        self.all_headers
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    /// Returns the body as a `String`, failing if it isn't valid UTF-8.
+    pub fn text(&self) -> Result<String, HttpError> {
+        String::from_utf8(self.body.clone()).map_err(HttpError::InvalidUtf8)
+    }
+
+    /// Deserializes the body as JSON.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, HttpError> {
+        serde_json::from_slice(&self.body).map_err(HttpError::Json)
+    }
+}
+
+/// Returns the offset where the body starts, i.e. right after the blank line
+/// that ends the header block, or `None` if the head isn't complete yet.
+pub(super) fn body_offset(raw: &[u8]) -> Option<usize> {
+    raw.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
+}
+
+/// Looks up the value of the first header called `name` (case-insensitive)
+/// in a raw head, without parsing the rest of the response.
+pub(super) fn find_header<'a>(head: &'a [u8], name: &str) -> Option<&'a str> {
+    std::str::from_utf8(head)
+        .ok()?
+        .split("\r\n")
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim())
+}
+
+/// Status code of a raw head, if its status line is valid.
+pub(super) fn find_status(head: &[u8]) -> Option<u16> {
+    let line = std::str::from_utf8(head).ok()?.split("\r\n").next()?;
+    parse_status_line(line).ok().map(|(_, status, _)| status)
+}
+
+/// Like `find_header`, but yields the value of every header called `name`,
+/// in the order they were received.
+pub(super) fn find_headers<'a>(head: &'a [u8], name: &'a str) -> impl Iterator<Item = &'a str> {
+    std::str::from_utf8(head)
+        .unwrap_or_default()
+        .split("\r\n")
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .filter(move |(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim())
+}
+
+/// Splits a status line like `HTTP/1.1 200 OK` into the version, the status
+/// code and the reason phrase. Only the first two spaces separate parts, so
+/// a reason phrase can contain spaces of its own. Anything that doesn't start
+/// with an `HTTP/` version and a three digit code is rejected instead of
+/// silently yielding a zero status, but a missing reason phrase is just empty.
+fn parse_status_line(line: &str) -> Result<(String, u16, String), HttpError> {
+    let mut parts = line.splitn(3, ' ');
+    let version = parts.next().unwrap_or_default();
+    if !version.starts_with("HTTP/") {
+        return Err(malformed(&format!("invalid status line: {line:?}")));
+    }
+
+    let status = parts
+        .next()
+        .filter(|code| code.len() == 3)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| malformed(&format!("invalid status code in: {line:?}")))?;
+    let reason = parts.next().unwrap_or_default().to_string();
+    Ok((version.to_string(), status, reason))
+}
+
+fn malformed(msg: &str) -> HttpError {
+    HttpError::MalformedResponse(msg.to_string())
+}
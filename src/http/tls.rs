@@ -0,0 +1,94 @@
+use super::HttpError;
+use mio::net::TcpStream;
+use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, RootCertStore};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::{Arc, OnceLock},
+};
+
+/// Shared by every TLS connection. Trusts the Mozilla root certificates
+/// bundled by `webpki-roots`.
+fn config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let roots = RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+            };
+            let config = ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
+
+/// A rustls client session on top of a non-blocking `TcpStream`. rustls
+/// doesn't do any I/O itself, so `read` shuttles TLS records between the
+/// session and the socket until there's plaintext to hand out or the socket
+/// would block.
+pub(super) struct TlsStream {
+    conn: ClientConnection,
+    socket: TcpStream,
+}
+
+impl TlsStream {
+    pub(super) fn new(socket: TcpStream, server_name: &str) -> Result<Self, HttpError> {
+        let name = ServerName::try_from(server_name.to_string())
+            .map_err(|e| HttpError::Connect(io::Error::new(ErrorKind::InvalidInput, e)))?;
+        let mut conn = ClientConnection::new(config(), name)
+            .map_err(|e| HttpError::Connect(io::Error::other(e)))?;
+        // The request is written before the handshake is done, so don't
+        // limit how much plaintext can be queued.
+        conn.set_buffer_limit(None);
+        Ok(Self { conn, socket })
+    }
+
+    pub(super) fn socket(&mut self) -> &mut TcpStream {
+        &mut self.socket
+    }
+
+    /// Queues `request` in the session. It's encrypted and written to the
+    /// socket by `read` once the handshake completes.
+    pub(super) fn send(&mut self, request: &[u8]) -> io::Result<()> {
+        self.conn.writer().write_all(request)
+    }
+
+    /// Writes as many pending TLS records as the socket accepts.
+    fn flush_tls(&mut self) -> io::Result<()> {
+        while self.conn.wants_write() {
+            match self.conn.write_tls(&mut self.socket) {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            self.flush_tls()?;
+
+            match self.conn.reader().read(buf) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+
+            // No plaintext buffered, so read more records off the socket.
+            // `WouldBlock` here is passed on so the caller waits for the next
+            // readiness event.
+            let eof = self.conn.read_tls(&mut self.socket)? == 0;
+            self.conn
+                .process_new_packets()
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            if eof {
+                // rustls tells a clean close from a cut off one: `Ok(0)` only
+                // if the server sent close_notify, `UnexpectedEof` otherwise
+                return self.conn.reader().read(buf);
+            }
+        }
+    }
+}
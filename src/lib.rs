@@ -0,0 +1,6 @@
+pub use error::RuntimeError;
+
+mod error;
+pub mod future;
+pub mod http;
+pub mod runtime;
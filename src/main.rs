@@ -1,9 +1,8 @@
-mod http;
-mod runtime;
+use a_runtime::runtime;
 use tokio::runtime::Runtime;
 
 fn main() {
-    let mut executor = runtime::init();
+    let mut executor = runtime::init(runtime::SchedulingPolicy::default());
     executor.block_on(async_main());
 }
 
@@ -1,11 +1,45 @@
-pub use executor::{spawn, Executor};
-pub use reactor::reactor;
+pub use blocking::spawn_blocking;
+pub use executor::{
+    handle, spawn, Executor, ExecutorStats, Handle, SchedulingPolicy, ShutdownHandle,
+};
+pub use io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+pub use join::{AbortHandle, JoinError, JoinHandle};
+pub use multi_thread::MultiThreadExecutor;
+#[cfg(feature = "tracing")]
+pub use observer::TracingObserver;
+pub use observer::Observer;
+pub use reactor::{reactor, Readiness};
+pub use sink::{Sink, SinkExt};
+pub use time::{interval, sleep, sleep_until, Interval, Sleep, Tick};
+pub use yield_now::{yield_now, YieldNow};
 
+mod blocking;
 mod executor;
+mod io;
+mod join;
+mod multi_thread;
+pub mod net;
+mod observer;
 mod reactor;
+mod sink;
+pub mod sync;
+mod time;
+mod yield_now;
 
-pub fn init() -> Executor {
+/// Starts the reactor and returns an `Executor` that polls ready tasks in
+/// the order given by `policy`.
+pub fn init(policy: SchedulingPolicy) -> Executor {
     reactor::start();
-    Executor::new()
+    let mut executor = Executor::new();
+    executor.set_policy(policy);
+    executor
+}
+
+/// Starts the reactor and returns a `MultiThreadExecutor` running tasks on
+/// `workers` threads. Use either this or `init`, since the reactor can only
+/// be started once.
+pub fn init_multi_thread(workers: usize) -> MultiThreadExecutor {
+    reactor::start();
+    MultiThreadExecutor::new(workers)
 }
 
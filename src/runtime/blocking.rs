@@ -0,0 +1,61 @@
+use super::{join, JoinHandle};
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+};
+
+/// Number of threads in the pool `spawn_blocking` runs closures on.
+const POOL_SIZE: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs `f` on a dedicated thread pool instead of the executor thread, so
+/// blocking calls and CPU-heavy work don't stall other tasks. The returned
+/// `JoinHandle` can be awaited from any task and wakes it, the same way
+/// any other `Waker` does, once `f` has returned.
+///
+/// Aborting the handle before `f` starts keeps it from running; once it's
+/// running it can't be interrupted.
+pub fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (handle, slot) = join::join_handle();
+    pool()
+        .send(Box::new(move || slot.run_blocking(f)))
+        .expect("blocking pool threads never exit");
+    handle
+}
+
+/// Starts the pool on first use, handing back the channel to send jobs on.
+fn pool() -> &'static Sender<Job> {
+    static POOL: OnceLock<Sender<Job>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..POOL_SIZE {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("blocking-{i}"))
+                .spawn(move || work(&receiver))
+                .expect("failed to spawn blocking pool thread");
+        }
+        sender
+    })
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // The lock is released before running the job so other threads can
+        // pick up the next one meanwhile.
+        let job = receiver.lock().unwrap().recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+}
@@ -1,64 +1,359 @@
+use super::{join, reactor, JoinError, JoinHandle, Observer};
 use std::{
     cell::{Cell, RefCell},
-    collections::HashMap,
-    future::Future,
+    collections::{HashMap, VecDeque},
+    future::{self, Future},
+    marker::PhantomData,
+    mem,
     pin::Pin,
-    sync::{Arc, Mutex},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     task::{Poll, Context, Wake, Waker},
     thread::{self, Thread},
+    time::{Duration, Instant},
 };
 
+// Tasks are type-erased down to `()` when spawned, so futures with
+// different output types can share the same `tasks` map.
 type Task = Pin<Box<dyn Future<Output = ()>>>;
 
 thread_local! {
     static CURRENT_EXEC: ExecutorCore = ExecutorCore::default();
 }
 
+/// Order in which tasks that are ready get polled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// The task woken most recently is polled first. This keeps hot tasks
+    /// running but can starve the task that has waited longest.
+    #[default]
+    Lifo,
+    /// Tasks are polled in the order they were woken. The reactor wakes the
+    /// task that has waited on it longest first, so with this policy tasks
+    /// waiting on I/O are polled in the order they started waiting.
+    Fifo,
+}
+
 #[derive(Default)]
 struct ExecutorCore {
     // Holds all top-lvl futures associated with the executor
     // on this thread. `RefCell` needed to to mutate static variable.
     tasks: RefCell<HashMap<usize, Task>>,
     // Stores IDs of tasks that should be polled by executor. An `Arc`
-    // (shared reference) to this `Vec` will be given to each `Waker`
+    // (shared reference) to this `VecDeque` will be given to each `Waker`
     // that this executor creates. Since the `Waker` will be sent to a
     // different thread and signal that a task is ready by adding the 
     // tasks ID to `ready_queue`, it needs to be wrapped in `Arc<Mutex<_>>`
-    ready_queue: Arc<Mutex<Vec<usize>>>,
+    ready_queue: Arc<Mutex<VecDeque<usize>>>,
+    // Which end of `ready_queue` the next task to poll is taken from
+    policy: Cell<SchedulingPolicy>,
     // Counter that gives out the next available ID. Should never hand out
     // the same ID twice for this executor instance. Since the executor 
     // instance will only be accessible on the same thread it was created,
     // `Cell` will suffice in giving us the needed internal mutability.
     next_id: Cell<usize>,
+    // IDs of finished tasks that are safe to hand out again, used before
+    // `next_id` so IDs stay compact however many tasks come and go
+    free_ids: RefCell<Vec<usize>>,
+    // The `Waker` each task in `tasks` is polled with. It's the same one on
+    // every poll, so once the task is done its reference count tells if a
+    // clone of it is still around somewhere.
+    wakers: RefCell<HashMap<usize, Arc<MyWaker>>>,
+    // IDs of finished tasks that can't be reused yet, since a clone of their
+    // `Waker` might still push them onto `ready_queue` and get whichever
+    // task reuses the ID polled for nothing
+    retired: RefCell<Vec<(usize, Arc<MyWaker>)>>,
+    // Number of tasks spawned so far, which `next_id` no longer tells once
+    // IDs are reused
+    spawned: Cell<usize>,
+    // Optional hook that gets notified about task lifecycle transitions.
+    observer: RefCell<Option<Box<dyn Observer>>>,
+    // Set once an `Executor` has been created on this thread
+    initialized: Cell<bool>,
+    // Set while `block_on` is running on this thread
+    running: Cell<bool>,
+    // How many times the thread parked waiting for a task to be woken
+    parks: Cell<usize>,
+    // ID of the task being polled right now, if any, and the `Waker` it's
+    // polled with
+    current: RefCell<Option<(usize, Waker)>>,
+    // Set through a `ShutdownHandle`, possibly from another thread, to make
+    // `block_on` drop all tasks and return.
+    shutdown: Arc<AtomicBool>,
+}
+
+/// Shuts down the executor of the thread it was created on. Can be cloned
+/// and sent to other threads, e.g. into a Ctrl-C handler.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+    thread: Thread,
+}
+
+impl ShutdownHandle {
+    /// Makes the executor drop all of its tasks and return from `block_on`
+    /// as soon as the task it's currently polling, if any, yields.
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+    }
 }
 
-pub fn spawn<F>(future: F)
-where 
-    F: Future<Output = ()> + 'static,
+/// Spawns `future` as a new top-lvl task on this thread's executor. Its
+/// output, whatever the type, is handed to the returned `JoinHandle` once
+/// it completes. The handle can also abort the task.
+///
+/// Can be called from within a running task, e.g. to spawn more work while
+/// processing a response. The new task is polled by the same `block_on`,
+/// after the task that spawned it yields.
+///
+/// Panics if no `Executor` was created on this thread, e.g. through
+/// `runtime::init`, since nothing would ever poll the task.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
 {
+    let (handle, slot) = join::join_handle();
+    let mut future = Box::pin(future);
+    let task = future::poll_fn(move |cx| slot.poll_task(future.as_mut(), cx));
+    CURRENT_EXEC.with(|e| {
+        assert!(
+            e.initialized.get(),
+            "`spawn` called outside a runtime context; create the executor with \
+             `runtime::init` on this thread first, or use `MultiThreadExecutor::spawn` \
+             from its worker threads"
+        );
+        let id = e.alloc_id();
+        e.tasks.borrow_mut().insert(id, Box::pin(task));
+        let waker = Arc::new(MyWaker {
+            id,
+            thread: thread::current(),
+            ready_queue: e.ready_queue.clone(),
+        });
+        e.wakers.borrow_mut().insert(id, waker);
+        e.ready_queue.lock().map(|mut q| q.push_back(id)).unwrap();
+        e.spawned.set(e.spawned.get() + 1);
+    });
+    handle
+}
+
+/// Returns a `Handle` for spawning tasks on this thread's executor.
+///
+/// Panics if no `Executor` was created on this thread, like `spawn`.
+pub fn handle() -> Handle {
     CURRENT_EXEC.with(|e| {
-        let id = e.next_id.get();
-        e.tasks.borrow_mut().insert(id, Box::pin(future));
-        e.ready_queue.lock().map(|mut q| q.push(id)).unwrap();
-        e.next_id.set(id + 1);
+        assert!(
+            e.initialized.get(),
+            "`handle` called outside a runtime context; create the executor with \
+             `runtime::init` on this thread first"
+        );
     });
+    Handle {
+        _not_send: PhantomData,
+    }
+}
+
+/// Spawns tasks on the executor of the thread it was obtained on, from
+/// `runtime::handle` or `Executor::handle`. It can be cloned and moved into
+/// closures and tasks, but not to other threads, since the tasks it spawns
+/// never leave this one.
+#[derive(Clone)]
+pub struct Handle {
+    _not_send: PhantomData<Rc<()>>,
+}
+
+impl Handle {
+    /// Same as the free `spawn` function.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        spawn(future)
+    }
+}
+
+impl ExecutorCore {
+    /// Hands out the ID for a new task, reusing the ID of a finished one if
+    /// nothing can refer to it anymore.
+    fn alloc_id(&self) -> usize {
+        if self.free_ids.borrow().is_empty() {
+            self.reclaim_ids();
+        }
+        self.free_ids.borrow_mut().pop().unwrap_or_else(|| {
+            let id = self.next_id.get();
+            self.next_id.set(id + 1);
+            id
+        })
+    }
+
+    /// Marks the ID of a task that finished or was dropped as no longer in
+    /// use. It's reused once `reclaim_ids` finds it safe to.
+    fn retire_id(&self, id: usize) {
+        if let Some(waker) = self.wakers.borrow_mut().remove(&id) {
+            self.retired.borrow_mut().push((id, waker));
+        }
+    }
+
+    /// Frees the retired IDs whose `Waker` has no clones left and that
+    /// aren't waiting in `ready_queue` from an earlier wake either.
+    fn reclaim_ids(&self) {
+        let queue = self.ready_queue.lock().unwrap();
+        let mut free = self.free_ids.borrow_mut();
+        self.retired.borrow_mut().retain(|(id, waker)| {
+            let stale = Arc::strong_count(waker) > 1 || queue.contains(id);
+            if !stale {
+                free.push(*id);
+            }
+            stale
+        });
+    }
+}
+
+/// Puts the task being polled on this thread back into the ready queue, on
+/// the end that's polled last under the current `SchedulingPolicy`, so all
+/// other ready tasks get polled first. Returns `false` when not called from
+/// within a task of this thread's executor, or when `waker` isn't the one
+/// the task is polled with, e.g. inside a `future::Tasks` set that only
+/// polls futures whose own `Waker` fired.
+pub(super) fn defer_current(waker: &Waker) -> bool {
+    CURRENT_EXEC.with(|e| {
+        let Some(id) = e
+            .current
+            .borrow()
+            .as_ref()
+            .filter(|(_, current)| waker.will_wake(current))
+            .map(|&(id, _)| id)
+        else {
+            return false;
+        };
+        let mut q = e.ready_queue.lock().unwrap();
+        match e.policy.get() {
+            SchedulingPolicy::Lifo => q.push_front(id),
+            SchedulingPolicy::Fifo => q.push_back(id),
+        }
+        true
+    })
+}
+
+/// Snapshot of an executor's counters, returned by `Executor::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutorStats {
+    /// Tasks that haven't completed yet, whether ready or waiting.
+    pub pending_tasks: usize,
+    /// Task IDs currently waiting in the ready queue to be polled.
+    pub ready_queue_depth: usize,
+    /// Tasks spawned since the executor was created.
+    pub total_spawned: usize,
+    /// Times the executor thread parked because no task was ready.
+    pub parks: usize,
 }
 
 pub struct Executor;
 
 impl Executor {
-    /// No initialization since everything is done lazily in `thread_local!`
+    /// Everything is done lazily in `thread_local!`, this only marks the
+    /// thread as having an executor so `spawn` can be used on it.
     pub fn new() -> Self {
+        CURRENT_EXEC.with(|e| e.initialized.set(true));
         Self {}
     }
 
-    /// Pops off an ID that's ready from the back of the `ready_queue` `Vec`
-    /// Since `Waker` pushes it's ID to the back of `ready_queue` and this pops
-    /// from the back as well, this is essentially a LIFO queue.
-    /// Using `VecDeque` from std lib would allow us to choose the order in which
-    /// we remove items from the queue if we wish to change that behavior.
+    /// Installs an `Observer` that's notified around every poll of a task
+    /// on this thread's executor, replacing any previous observer.
+    pub fn set_observer(&mut self, observer: impl Observer + 'static) {
+        CURRENT_EXEC.with(|e| *e.observer.borrow_mut() = Some(Box::new(observer)));
+    }
+
+    /// Sets the order in which ready tasks on this thread get polled.
+    pub fn set_policy(&mut self, policy: SchedulingPolicy) {
+        CURRENT_EXEC.with(|e| e.policy.set(policy));
+    }
+
+    /// Returns the current counters of this thread's executor. Meant to be
+    /// called between `block_on` calls or from within a task; the task being
+    /// polled at that moment isn't counted in `pending_tasks`, since it's
+    /// taken out of `tasks` while it runs.
+    pub fn stats(&self) -> ExecutorStats {
+        CURRENT_EXEC.with(|e| ExecutorStats {
+            pending_tasks: e.tasks.borrow().len(),
+            ready_queue_depth: e.ready_queue.lock().unwrap().len(),
+            total_spawned: e.spawned.get(),
+            parks: e.parks.get(),
+        })
+    }
+
+    /// Returns a handle that spawns tasks on this thread's executor.
+    pub fn handle(&self) -> Handle {
+        handle()
+    }
+
+    /// Returns a handle that shuts down this thread's executor.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            flag: CURRENT_EXEC.with(|e| e.shutdown.clone()),
+            thread: thread::current(),
+        }
+    }
+
+    /// Shuts down this thread's executor, e.g. from within one of its tasks.
+    /// See `ShutdownHandle::shutdown`.
+    pub fn shutdown(&self) {
+        self.shutdown_handle().shutdown();
+    }
+
+    fn shutdown_requested(&self) -> bool {
+        CURRENT_EXEC.with(|e| e.shutdown.load(Ordering::SeqCst))
+    }
+
+    /// If a shutdown was requested, drops all tasks and clears the request
+    /// so the executor can be used again. Returns whether it was.
+    fn take_shutdown(&self) -> bool {
+        if !CURRENT_EXEC.with(|e| e.shutdown.swap(false, Ordering::SeqCst)) {
+            return false;
+        }
+        self.drop_tasks();
+        true
+    }
+
+    /// Drops every task, ready or not.
+    fn drop_tasks(&self) {
+        CURRENT_EXEC.with(|e| {
+            e.ready_queue.lock().unwrap().clear();
+            // Dropped outside the borrow, since a future's `Drop` might
+            // spawn or otherwise touch `tasks`
+            let tasks = mem::take(&mut *e.tasks.borrow_mut());
+            drop(tasks);
+            let ids: Vec<usize> = e.wakers.borrow().keys().copied().collect();
+            ids.into_iter().for_each(|id| e.retire_id(id));
+        })
+    }
+
+    /// Runs `f` with the installed `Observer`, if any.
+    fn observe(&self, f: impl FnOnce(&dyn Observer)) {
+        CURRENT_EXEC.with(|e| {
+            if let Some(observer) = e.observer.borrow().as_deref() {
+                f(observer);
+            }
+        });
+    }
+
+    /// Pops off an ID that's ready from the `ready_queue` `VecDeque`.
+    /// Since `Waker` pushes it's ID to the back of `ready_queue`, popping
+    /// from the back makes it a LIFO queue and popping from the front a FIFO
+    /// queue. Which one is used depends on the `SchedulingPolicy`.
     fn pop_ready(&self) -> Option<usize> {
-        CURRENT_EXEC.with(|q| q.ready_queue.lock().map(|mut q| q.pop()).unwrap())
+        CURRENT_EXEC.with(|e| {
+            let mut q = e.ready_queue.lock().unwrap();
+            match e.policy.get() {
+                SchedulingPolicy::Lifo => q.pop_back(),
+                SchedulingPolicy::Fifo => q.pop_front(),
+            }
+        })
     }
 
     /// Takes `id` of a top-lvl future, removes it from `tasks` and returns it if
@@ -68,13 +363,10 @@ impl Executor {
         CURRENT_EXEC.with(|q| q.tasks.borrow_mut().remove(&id))
     }
 
-    /// Creates a new `Waker` instance.
+    /// Returns the `Waker` of the task with `id`, created when it was
+    /// spawned.
     fn get_waker(&self, id: usize) -> Arc<MyWaker> {
-        Arc::new(MyWaker {
-            id,
-            thread: thread::current(),
-            ready_queue: CURRENT_EXEC.with(|q| q.ready_queue.clone()),
-        })
+        CURRENT_EXEC.with(|q| q.wakers.borrow()[&id].clone())
     }
 
     /// Takes an `id` property and a `Task` property and inserts them into `tasks`
@@ -88,7 +380,7 @@ impl Executor {
     }
 
     /// Entry point to `Executor`:
-    /// 1. Spawns the future received.
+    /// 1. Spawns the future received, keeping its `JoinHandle` to get the output.
     /// 2. Loop as long as our asynchronous programs is running.
     /// 3. On every iteration, create an inner loop that runs as long as 
     ///    there are tasks in `ready_queue`.
@@ -98,21 +390,93 @@ impl Executor {
     /// 5. Create a `Waker` instance to pass into `Future::poll()`. This `Waker`
     ///    instance now holds the `id` property that IDs this specific `Future` and
     ///    a handle to the thread it's currently running on.
-    /// 6. Call `Future::poll`. If `NotReady` insert back into `tasks`. If `Ready` it
-    ///    continues to the next item in the `ready_queue`. The `Future` will be dropped
-    ///    before next iteration of `while let` loop because it took ownership.
+    /// 6. Call `Future::poll`, notifying the `Observer` before and after. If `NotReady`
+    ///    insert back into `tasks`. If `Ready` it continues to the next item in the
+    ///    `ready_queue`. The `Future` will be dropped before next iteration of
+    ///    `while let` loop because it took ownership.
     /// 7. After polling all task in `ready_queue` get `tasks` count to see how many left.
     /// 8. If there are tasks left call `thread::park()`. This will yield control to the 
     ///    OS scheduler, and `Executor` does nothing until it's woken up again.
-    /// 9. If there are no tasks left the program is done and exit the main loop.
-    pub fn block_on<F>(&mut self, future: F)
+    /// 9. If there are no tasks left the program is done and exit the main loop,
+    ///    returning the output of the future received. If the future received
+    ///    panicked, the panic is raised again here instead.
+    ///
+    /// Panics if the executor is shut down before the future received has
+    /// completed; use `try_block_on` if that's expected.
+    pub fn block_on<F>(&mut self, future: F) -> F::Output
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        self.try_block_on(future)
+            .expect("executor was shut down before the future passed to `block_on` completed")
+    }
+
+    /// Like `block_on`, but returns `None` instead of panicking if the
+    /// executor is shut down before the future received completes. Shutting
+    /// down drops all remaining tasks and is checked between polls.
+    ///
+    /// Both panic when called from within a task, i.e. while `block_on` is
+    /// already running on this thread. The inner loop would poll the outer
+    /// loop's tasks while the polling task is blocked, so use `spawn` and
+    /// `.await` the `JoinHandle` instead.
+    pub fn try_block_on<F>(&mut self, future: F) -> Option<F::Output>
     where
-        F: Future<Output = ()> + 'static,
+        F: Future + 'static,
+        F::Output: 'static,
     {
-        spawn(future);
+        self.run(future, None)
+    }
+
+    /// Like `block_on`, but gives up once `timeout` has passed, returning
+    /// `None` unless the future received completed by then. The deadline
+    /// is a reactor timer that unparks this thread, so it's kept even if
+    /// nothing else wakes any task. Tasks still pending at the deadline are
+    /// dropped, the same way as when the executor is shut down.
+    ///
+    /// Needs the reactor to be running, see `runtime::init`.
+    pub fn block_on_timeout<F>(&mut self, future: F, timeout: Duration) -> Option<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let deadline = Instant::now() + timeout;
+        let timer = reactor().next_id();
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        reactor().set_waker(&Context::from_waker(&waker), timer);
+        // Only fails to interrupt the event loop, the deadline is set anyway
+        let _ = reactor().set_timeout(deadline, timer);
+
+        let output = self.run(future, Some(deadline));
+        reactor().release_id(timer);
+        output
+    }
+
+    /// Runs the loop described on `block_on`. With a `deadline` it ends once
+    /// that has passed as well, whether tasks are left or not.
+    fn run<F>(&mut self, future: F, deadline: Option<Instant>) -> Option<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let _running = RunningGuard::enter();
+        let handle = spawn(future);
 
         loop {
+            if self.take_shutdown() {
+                let name = thread::current().name().unwrap_or_default().to_string();
+                log::debug!("{name}: Shut down");
+                // The future received might have completed before that
+                return handle.try_take().and_then(Result::ok);
+            }
+
             while let Some(id) = self.pop_ready() {
+                // Tasks that keep waking themselves would never let the
+                // loop end, so the deadline can't wait for that
+                if self.shutdown_requested() || deadline.is_some_and(|d| Instant::now() >= d) {
+                    break;
+                }
+
                 let mut future = match self.get_future(id) {
                     Some(f) => f,
                     // guard against false wakeups
@@ -122,39 +486,100 @@ impl Executor {
                 let waker: Waker = self.get_waker(id).into();
                 let mut cx = Context::from_waker(&waker);
 
-                match future.as_mut().poll(&mut cx) {
+                self.observe(|o| o.on_poll_begin(id));
+                CURRENT_EXEC.with(|e| *e.current.borrow_mut() = Some((id, waker.clone())));
+                let poll = future.as_mut().poll(&mut cx);
+                CURRENT_EXEC.with(|e| *e.current.borrow_mut() = None);
+                self.observe(|o| o.on_poll_end(id, poll.is_ready()));
+
+                match poll {
                     Poll::Pending => self.insert_task(id, future),
-                    Poll::Ready(_) => continue,
+                    Poll::Ready(_) => {
+                        // Both hold on to the waker, so they have to go
+                        // first or it'd count as a clone still around
+                        drop(future);
+                        drop(waker);
+                        CURRENT_EXEC.with(|e| e.retire_id(id));
+                    }
                 }
             }
 
+            if self.shutdown_requested() {
+                continue;
+            }
+
             let task_count = self.task_count();
             let name = thread::current().name().unwrap_or_default().to_string();
 
-            if task_count > 0 {
-                println!("{name}: {task_count} pending tasks. Sleep until notified.");
+            if task_count > 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                log::debug!("{name}: Deadline passed with {task_count} pending tasks");
+                self.drop_tasks();
+                // The future received might have completed before that
+                return handle.try_take().and_then(Result::ok);
+            } else if task_count > 0 {
+                log::trace!("{name}: {task_count} pending tasks. Sleep until notified.");
+                CURRENT_EXEC.with(|e| e.parks.set(e.parks.get() + 1));
                 thread::park();
             } else {
-                println!("{name}: All tasks are finished");
+                log::debug!("{name}: All tasks are finished");
                 break;
             }
         }
+
+        match handle.try_take() {
+            Some(Ok(output)) => Some(output),
+            Some(Err(JoinError::Panicked(msg))) => panic!("root future panicked: {msg}"),
+            // Nothing else has the root task's handle to abort it
+            Some(Err(JoinError::Cancelled)) | None => {
+                unreachable!("all tasks finished, so the root future has completed")
+            }
+        }
     }
 
 }
 
+/// Marks `block_on` as running on this thread for as long as it's alive,
+/// including while unwinding from a panic.
+struct RunningGuard;
+
+impl RunningGuard {
+    fn enter() -> Self {
+        CURRENT_EXEC.with(|e| {
+            assert!(
+                !e.running.replace(true),
+                "`block_on` called while already running on this thread; \
+                 nested `block_on` isn't supported, `spawn` the future and \
+                 `.await` its `JoinHandle` instead"
+            );
+        });
+        RunningGuard
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        CURRENT_EXEC.with(|e| e.running.set(false));
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone)]
 pub struct MyWaker {
     // Handle to the current Thread
     thread: Thread,
     // Identifies which task this Waker is associated with
     id: usize,
-    // Reference to a `Vec<usize> that can be shared between 
+    // Reference to a `VecDeque<usize>` that can be shared between 
     // threads. `usize` represents the ID of a task that's in
     // the ready queue. This object is shared with the executor
     // to push the task ID associated with the `Waker` onto that
     // queue when it's ready.
-    ready_queue: Arc<Mutex<Vec<usize>>>,
+    ready_queue: Arc<Mutex<VecDeque<usize>>>,
 }
 
 impl Wake for MyWaker {
@@ -167,8 +592,18 @@ impl Wake for MyWaker {
     fn wake(self: Arc<Self>) {
         self.ready_queue
             .lock()
-            .map(|mut q| q.push(self.id))
+            .map(|mut q| q.push_back(self.id))
             .unwrap();
         self.thread.unpark();
     }
+}
+
+/// Unparks the executor thread without queueing a task, so
+/// `block_on_timeout` gets to check its deadline.
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
 }
\ No newline at end of file
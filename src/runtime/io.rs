@@ -0,0 +1,131 @@
+use std::{
+    future::{self, Future},
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A source of bytes that can be read without blocking. When no bytes are
+/// available, `poll_read` returns `Pending` and wakes the task once there
+/// are.
+pub trait AsyncRead {
+    /// Reads into `buf`, returning how many bytes were read. 0 means the
+    /// end of the stream was reached.
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8])
+        -> Poll<io::Result<usize>>;
+}
+
+/// A sink for bytes that can be written without blocking. When there's no
+/// room for more, `poll_write` returns `Pending` and wakes the task once
+/// there is.
+pub trait AsyncWrite {
+    /// Writes from `buf`, returning how many bytes were written.
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>>;
+
+    /// Waits until everything written so far has been handed on.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>>;
+}
+
+/// Convenience methods that turn `AsyncRead::poll_read` into futures that
+/// can be `.await`ed, written once for every reader.
+pub trait AsyncReadExt: AsyncRead {
+    /// Resolves to the number of bytes read into `buf`.
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        future::poll_fn(move |cx| Pin::new(&mut *self).poll_read(cx, buf))
+    }
+
+    /// Reads until the end of the stream, appending everything to `buf`.
+    /// Resolves to the number of bytes read.
+    fn read_to_end<'a>(
+        &'a mut self,
+        buf: &'a mut Vec<u8>,
+    ) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let start = buf.len();
+            let mut chunk = [0u8; 4096];
+            loop {
+                match self.read(&mut chunk).await? {
+                    0 => return Ok(buf.len() - start),
+                    n => buf.extend_from_slice(&chunk[..n]),
+                }
+            }
+        }
+    }
+
+    /// Reads up to and including the next `\n`, or to the end of the stream,
+    /// and appends it to `buf`. Resolves to the number of bytes read, so 0
+    /// means the stream had already ended. The reader has no buffer to keep
+    /// what comes after the newline in, so this reads one byte at a time.
+    ///
+    /// Fails with `InvalidData` if the line isn't valid UTF-8, in which case
+    /// `buf` is left as it was.
+    fn read_line<'a>(
+        &'a mut self,
+        buf: &'a mut String,
+    ) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let mut line = vec![];
+            let mut byte = [0u8];
+            while self.read(&mut byte).await? == 1 {
+                line.push(byte[0]);
+                if byte[0] == b'\n' {
+                    break;
+                }
+            }
+            let line =
+                String::from_utf8(line).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            buf.push_str(&line);
+            Ok(line.len())
+        }
+    }
+}
+
+impl<R: AsyncRead + ?Sized> AsyncReadExt for R {}
+
+/// Convenience methods that turn the `poll_*` methods of an `AsyncWrite`
+/// into futures that can be `.await`ed.
+pub trait AsyncWriteExt: AsyncWrite {
+    /// Resolves to the number of bytes of `buf` written.
+    fn write<'a>(&'a mut self, buf: &'a [u8]) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        future::poll_fn(move |cx| Pin::new(&mut *self).poll_write(cx, buf))
+    }
+
+    /// Writes all of `buf`, however many writes that takes.
+    fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> impl Future<Output = io::Result<()>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let mut written = 0;
+            while written < buf.len() {
+                match self.write(&buf[written..]).await? {
+                    0 => return Err(ErrorKind::WriteZero.into()),
+                    n => written += n,
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Resolves once everything written so far has been handed on.
+    fn flush(&mut self) -> impl Future<Output = io::Result<()>> + '_
+    where
+        Self: Unpin,
+    {
+        future::poll_fn(move |cx| Pin::new(&mut *self).poll_flush(cx))
+    }
+}
+
+impl<W: AsyncWrite + ?Sized> AsyncWriteExt for W {}
@@ -0,0 +1,216 @@
+use std::{
+    any::Any,
+    error::Error,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// Why a spawned task didn't produce an output.
+#[derive(Debug)]
+pub enum JoinError {
+    /// The task was aborted through its `JoinHandle` or an `AbortHandle`.
+    Cancelled,
+    /// The task panicked, with the given panic message.
+    Panicked(String),
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::Cancelled => write!(f, "task was cancelled"),
+            JoinError::Panicked(msg) => write!(f, "task panicked: {msg}"),
+        }
+    }
+}
+
+impl Error for JoinError {}
+
+struct Shared<T> {
+    output: Option<Result<T, JoinError>>,
+    // Set once `output` has been filled in, even after it's been taken
+    finished: bool,
+    // Most recent `Waker` to wake once `output` has been filled in
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct AbortState {
+    aborted: bool,
+    // `Waker` the task was last polled with, woken to let it see `aborted`
+    waker: Option<Waker>,
+}
+
+/// Resolves to the output of a spawned task once it has completed, or to
+/// a `JoinError` if it was aborted first or panicked. Dropping the handle
+/// detaches the task; it keeps running to completion and its output is
+/// dropped.
+pub struct JoinHandle<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    abort: AbortHandle,
+}
+
+/// Aborts a spawned task without taking its output. Can be cloned and sent
+/// to other threads.
+#[derive(Clone)]
+pub struct AbortHandle {
+    state: Arc<Mutex<AbortState>>,
+}
+
+/// The task's side of a `JoinHandle`, used to hand over its output.
+pub(super) struct JoinSlot<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    abort: Arc<Mutex<AbortState>>,
+}
+
+/// Creates a connected `JoinHandle` and `JoinSlot` pair.
+pub(super) fn join_handle<T>() -> (JoinHandle<T>, JoinSlot<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        output: None,
+        finished: false,
+        waker: None,
+    }));
+    let abort = AbortHandle {
+        state: Arc::new(Mutex::new(AbortState::default())),
+    };
+    let slot = JoinSlot {
+        shared: shared.clone(),
+        abort: abort.state.clone(),
+    };
+    (JoinHandle { shared, abort }, slot)
+}
+
+impl<T> JoinHandle<T> {
+    /// Aborts the task. See `AbortHandle::abort`.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+
+    /// Returns an `AbortHandle` for the task, which can abort it even after
+    /// this handle has been awaited or dropped.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Takes the output if the task has completed, without waiting.
+    pub(super) fn try_take(&self) -> Option<Result<T, JoinError>> {
+        self.shared.lock().unwrap().output.take()
+    }
+}
+
+impl AbortHandle {
+    /// Aborts the task. It's woken and, instead of being polled again, is
+    /// removed from the executor and its future dropped, which releases
+    /// whatever the future held. Aborting a task that already finished does
+    /// nothing.
+    pub fn abort(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.aborted = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> JoinSlot<T> {
+    /// Polls the task's `future`, handing its output over once it's ready.
+    /// Resolves without polling `future` again if the task was aborted.
+    /// A panic in `future` is caught and handed over as the task's error, so
+    /// it ends this task but not the executor running it.
+    pub(super) fn poll_task<F>(&self, future: Pin<&mut F>, cx: &mut Context) -> Poll<()>
+    where
+        F: Future<Output = T>,
+    {
+        let aborted = {
+            let mut abort = self.abort.lock().unwrap();
+            if !abort.aborted {
+                abort.waker = Some(cx.waker().clone());
+            }
+            abort.aborted
+        };
+        if aborted {
+            self.complete(Err(JoinError::Cancelled));
+            return Poll::Ready(());
+        }
+
+        // The future is never polled again after a panic, so whatever state
+        // the panic left it in can't be observed.
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => {
+                self.complete(Ok(output));
+                Poll::Ready(())
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => {
+                self.complete(Err(panicked(payload)));
+                Poll::Ready(())
+            }
+        }
+    }
+
+    /// Calls `f` and hands over what it returns, unless the task was
+    /// aborted before it got to run.
+    pub(super) fn run_blocking(&self, f: impl FnOnce() -> T) {
+        if self.abort.lock().unwrap().aborted {
+            self.complete(Err(JoinError::Cancelled));
+        } else {
+            let output = panic::catch_unwind(AssertUnwindSafe(f)).map_err(panicked);
+            self.complete(output);
+        }
+    }
+
+    /// Stores `output` and wakes the task awaiting the `JoinHandle`, if any.
+    /// The task's own `Waker` is let go of, as it won't be polled again.
+    fn complete(&self, output: Result<T, JoinError>) {
+        self.abort.lock().unwrap().waker = None;
+        let mut shared = self.shared.lock().unwrap();
+        shared.output = Some(output);
+        shared.finished = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Drop for JoinSlot<T> {
+    /// A task dropped before finishing, e.g. by an executor shutting down,
+    /// counts as cancelled so its `JoinHandle` doesn't wait forever.
+    fn drop(&mut self) {
+        if !self.shared.lock().unwrap().finished {
+            self.complete(Err(JoinError::Cancelled));
+        }
+    }
+}
+
+/// Logs a caught panic and turns its payload into a `JoinError`.
+fn panicked(payload: Box<dyn Any + Send>) -> JoinError {
+    let msg = match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "Box<dyn Any>".to_string(),
+        },
+    };
+    let name = thread::current().name().unwrap_or_default().to_string();
+    log::warn!("{name}: task panicked: {msg}");
+    JoinError::Panicked(msg)
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use super::{join, JoinError, JoinHandle};
+use std::{
+    collections::{HashMap, VecDeque},
+    future::{self, Future},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    task::{Context, Wake, Waker},
+    thread::{self, Thread},
+};
+
+// Unlike the single-threaded executor, tasks can move between worker
+// threads, so they have to be `Send`.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct TaskCell {
+    id: usize,
+    // `None` once the task has completed. Locked while the task is polled,
+    // so a task woken during its own poll and picked up by another worker
+    // waits for that poll to finish.
+    future: Mutex<Option<Task>>,
+    // Set while the task's id sits in a ready queue, so it's only queued
+    // once no matter how many times it's woken before being polled.
+    scheduled: AtomicBool,
+}
+
+struct Shared {
+    // All tasks that haven't completed yet, keyed by id
+    tasks: Mutex<HashMap<usize, Arc<TaskCell>>>,
+    // One ready queue per worker. A worker takes from the front of its own
+    // queue and steals from the back of the others when it runs dry.
+    queues: Vec<Mutex<VecDeque<usize>>>,
+    // Handles to unpark the worker threads, set once they're all spawned
+    workers: OnceLock<Vec<Thread>>,
+    next_id: AtomicUsize,
+    // The thread waiting in `block_on`, unparked once no tasks are left
+    waiting: Mutex<Option<Thread>>,
+}
+
+/// An executor that runs tasks on a fixed pool of worker threads. Each
+/// worker has its own ready queue; a worker that runs out of tasks steals
+/// them from the other workers' queues before going to sleep.
+///
+/// Cloning gives another handle to the same pool, which is how tasks get
+/// to spawn more tasks onto it.
+#[derive(Clone)]
+pub struct MultiThreadExecutor {
+    shared: Arc<Shared>,
+}
+
+impl MultiThreadExecutor {
+    /// Spawns `workers` worker threads, named `worker-0`, `worker-1` and so
+    /// on. They live for as long as the program does.
+    pub(super) fn new(workers: usize) -> Self {
+        assert!(workers > 0, "a multi-threaded executor needs a worker");
+
+        let shared = Arc::new(Shared {
+            tasks: Mutex::new(HashMap::new()),
+            queues: (0..workers).map(|_| Mutex::new(VecDeque::new())).collect(),
+            workers: OnceLock::new(),
+            next_id: AtomicUsize::new(0),
+            waiting: Mutex::new(None),
+        });
+
+        let threads = (0..workers)
+            .map(|index| {
+                let shared = shared.clone();
+                thread::Builder::new()
+                    .name(format!("worker-{index}"))
+                    .spawn(move || shared.work(index))
+                    .expect("failed to spawn worker thread")
+                    .thread()
+                    .clone()
+            })
+            .collect();
+        // Nothing can be spawned before `new` returns, so no worker needs
+        // `workers` before it's set.
+        let _ = shared.workers.set(threads);
+
+        Self { shared }
+    }
+
+    /// Spawns `future` onto the worker with the fewest tasks ready to run,
+    /// returning a `JoinHandle` for its output.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (handle, slot) = join::join_handle();
+        let mut future = Box::pin(future);
+        let task = future::poll_fn(move |cx| slot.poll_task(future.as_mut(), cx));
+
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let cell = Arc::new(TaskCell {
+            id,
+            future: Mutex::new(Some(Box::pin(task))),
+            scheduled: AtomicBool::new(true),
+        });
+        self.shared.tasks.lock().unwrap().insert(id, cell);
+        self.shared.schedule(id, self.shared.least_loaded());
+        handle
+    }
+
+    /// Spawns `future` and blocks the calling thread until every task on
+    /// the pool has finished, then returns the output of `future`. Panics if
+    /// `future` panicked.
+    pub fn block_on<F>(&mut self, future: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        *self.shared.waiting.lock().unwrap() = Some(thread::current());
+        let handle = self.spawn(future);
+
+        // Unparked by the worker that completes the last task
+        while !self.shared.tasks.lock().unwrap().is_empty() {
+            thread::park();
+        }
+        *self.shared.waiting.lock().unwrap() = None;
+
+        match handle.try_take() {
+            Some(Ok(output)) => output,
+            Some(Err(JoinError::Panicked(msg))) => panic!("root future panicked: {msg}"),
+            // Nothing else has the root task's handle to abort it
+            Some(Err(JoinError::Cancelled)) | None => {
+                unreachable!("all tasks finished, so the root future has completed")
+            }
+        }
+    }
+}
+
+impl Shared {
+    /// Main loop of worker `index`: run ready tasks, or sleep until woken
+    /// when there are none to run or steal.
+    fn work(self: Arc<Self>, index: usize) {
+        loop {
+            match self.next_task(index) {
+                Some(id) => self.run(id, index),
+                None => thread::park(),
+            }
+        }
+    }
+
+    /// Takes the next id from worker `index`'s own queue, falling back to
+    /// stealing one from the other workers' queues.
+    fn next_task(&self, index: usize) -> Option<usize> {
+        let mut own = self.queues[index].lock().unwrap();
+        if let Some(id) = own.pop_front() {
+            // Let a neighbour know there's more work here to steal
+            if !own.is_empty() {
+                self.unpark((index + 1) % self.queues.len());
+            }
+            return Some(id);
+        }
+        drop(own);
+
+        let count = self.queues.len();
+        (1..count)
+            .map(|offset| (index + offset) % count)
+            .find_map(|victim| self.queues[victim].lock().unwrap().pop_back())
+    }
+
+    /// Polls task `id` on worker `index`. Its `Waker` schedules it back
+    /// onto the same worker, where it likely still has a warm cache.
+    fn run(self: &Arc<Self>, id: usize, index: usize) {
+        // Guard against false wakeups
+        let Some(cell) = self.tasks.lock().unwrap().get(&id).cloned() else {
+            return;
+        };
+
+        // Cleared before polling so a wake during the poll queues it again
+        cell.scheduled.store(false, Ordering::SeqCst);
+        let mut future = cell.future.lock().unwrap();
+        let Some(task) = future.as_mut() else {
+            return;
+        };
+
+        let waker: Waker = Arc::new(TaskWaker {
+            cell: cell.clone(),
+            worker: index,
+            shared: self.clone(),
+        })
+        .into();
+        let mut cx = Context::from_waker(&waker);
+        if task.as_mut().poll(&mut cx).is_pending() {
+            return;
+        }
+
+        *future = None;
+        drop(future);
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.remove(&id);
+        if tasks.is_empty() {
+            if let Some(thread) = self.waiting.lock().unwrap().as_ref() {
+                thread.unpark();
+            }
+        }
+    }
+
+    fn schedule(&self, id: usize, index: usize) {
+        self.queues[index].lock().unwrap().push_back(id);
+        self.unpark(index);
+    }
+
+    fn least_loaded(&self) -> usize {
+        (0..self.queues.len())
+            .min_by_key(|&index| self.queues[index].lock().unwrap().len())
+            .unwrap()
+    }
+
+    fn unpark(&self, index: usize) {
+        if let Some(workers) = self.workers.get() {
+            workers[index].unpark();
+        }
+    }
+}
+
+struct TaskWaker {
+    cell: Arc<TaskCell>,
+    // Worker that last polled the task, whose queue it's put back on
+    worker: usize,
+    shared: Arc<Shared>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.cell.scheduled.swap(true, Ordering::SeqCst) {
+            self.shared.schedule(self.cell.id, self.worker);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Sockets driven by the reactor, for protocols other than HTTP.
+
+mod tcp;
+mod udp;
+
+pub use tcp::{AcceptFuture, ConnectFuture, ReadFuture, TcpListener, TcpStream, WriteFuture};
+pub use udp::{UdpRecvFuture, UdpSocket};
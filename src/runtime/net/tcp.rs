@@ -0,0 +1,337 @@
+use crate::runtime::{reactor, AsyncRead, AsyncWrite, Readiness, Sink};
+use mio::Interest;
+use std::{
+    future::Future,
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    net::{Shutdown, SocketAddr},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A TCP connection whose reads and writes wait on the reactor instead of
+/// blocking. It's registered for both readable and writable events for as
+/// long as it's alive.
+///
+/// As a `Sink<Vec<u8>>` it takes one buffer at a time, and won't take the
+/// next until the previous one is written out in full.
+pub struct TcpStream {
+    stream: mio::net::TcpStream,
+    id: usize,
+    // Rest of the last buffer handed over through `Sink::start_send`
+    unsent: Vec<u8>,
+}
+
+impl TcpStream {
+    fn new(stream: mio::net::TcpStream, id: usize) -> Self {
+        Self {
+            stream,
+            id,
+            unsent: Vec::new(),
+        }
+    }
+
+    /// Resolves to a stream connected to `addr`. The connect doesn't block,
+    /// the task waits on the reactor for the handshake to finish instead.
+    pub fn connect(addr: SocketAddr) -> ConnectFuture {
+        ConnectFuture {
+            addr,
+            stream: None,
+            id: Some(reactor().next_id()),
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// Resolves to the number of bytes read into `buf`, once at least one
+    /// is available. 0 means the peer closed its end of the connection.
+    pub fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFuture<'a> {
+        ReadFuture { stream: self, buf }
+    }
+
+    /// Resolves to the number of bytes of `buf` written, once there's room
+    /// for at least one in the socket's send buffer.
+    pub fn write<'a>(&'a mut self, buf: &'a [u8]) -> WriteFuture<'a> {
+        WriteFuture { stream: self, buf }
+    }
+
+    /// Runs `op` until it doesn't fail with `WouldBlock`, waiting on the
+    /// reactor in between.
+    fn poll_io<T>(
+        &mut self,
+        cx: &mut Context,
+        mut op: impl FnMut(&mut mio::net::TcpStream) -> io::Result<T>,
+    ) -> Poll<io::Result<T>> {
+        loop {
+            match op(&mut self.stream) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    reactor().check_running()?;
+                    reactor().set_waker(cx, self.id);
+                    // An event that arrived since `op` ran found no waker to
+                    // wake, but its readiness was kept, so try again
+                    if reactor().take_readiness(self.id) == Readiness::default() {
+                        return Poll::Pending;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    /// Writes `buf` once whatever `start_send` left behind is out, so bytes
+    /// go out in the order they were handed over.
+    fn poll_write_buf(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.poll_unsent(cx) {
+            Poll::Ready(Ok(())) => self.poll_io(cx, |stream| stream.write(buf)),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Writes until nothing from `start_send` is left.
+    fn poll_unsent(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while !self.unsent.is_empty() {
+            let unsent = mem::take(&mut self.unsent);
+            let result = self.poll_io(cx, |stream| stream.write(&unsent));
+            self.unsent = unsent;
+            match result {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => {
+                    self.unsent.drain(..n);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_io(cx, |stream| stream.read(buf))
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_buf(cx, buf)
+    }
+
+    /// Writes go straight to the socket, only what was handed over through
+    /// `Sink::start_send` may still need flushing.
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.poll_unsent(cx)
+    }
+}
+
+impl Sink<Vec<u8>> for TcpStream {
+    type Error = io::Error;
+
+    /// Ready once the previous buffer has been written out.
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.poll_unsent(cx)
+    }
+
+    /// Nothing is written yet, that happens in the next `poll_ready`,
+    /// `poll_close` or write.
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> io::Result<()> {
+        self.unsent.extend_from_slice(&item);
+        Ok(())
+    }
+
+    /// Writes out the last buffer and then shuts down the writing half, so
+    /// the peer reads end of stream.
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match self.poll_unsent(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(self.stream.shutdown(Shutdown::Write)),
+            other => other,
+        }
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        let _ = reactor().deregister(&mut self.stream, self.id);
+        reactor().release_id(self.id);
+    }
+}
+
+/// Listens for incoming TCP connections, waiting on the reactor for them
+/// to arrive instead of blocking.
+pub struct TcpListener {
+    listener: mio::net::TcpListener,
+    id: usize,
+}
+
+impl TcpListener {
+    /// Binds a listener to `addr`, registering it with the reactor. Port 0
+    /// lets the OS pick one.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let mut listener = mio::net::TcpListener::bind(addr)?;
+        let id = reactor().next_id();
+        reactor().register(&mut listener, Interest::READABLE, id)?;
+        Ok(Self { listener, id })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Resolves to the next connection accepted and the address of its
+    /// peer. Every stream accepted gets a reactor id of its own.
+    pub fn accept(&mut self) -> AcceptFuture<'_> {
+        AcceptFuture { listener: self }
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        let _ = reactor().deregister(&mut self.listener, self.id);
+        reactor().release_id(self.id);
+    }
+}
+
+/// Future returned by `TcpListener::accept`.
+pub struct AcceptFuture<'a> {
+    listener: &'a mut TcpListener,
+}
+
+impl Future for AcceptFuture<'_> {
+    type Output = io::Result<(TcpStream, SocketAddr)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let listener = &mut *self.listener;
+        loop {
+            match listener.listener.accept() {
+                Ok((mut stream, addr)) => {
+                    let id = reactor().next_id();
+                    let interest = Interest::READABLE | Interest::WRITABLE;
+                    reactor().register(&mut stream, interest, id)?;
+                    return Poll::Ready(Ok((TcpStream::new(stream, id), addr)));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    reactor().check_running()?;
+                    reactor().set_waker(cx, listener.id);
+                    // Same as for `TcpStream`, don't miss a connection that
+                    // arrived before the waker was stored
+                    if reactor().take_readiness(listener.id) == Readiness::default() {
+                        return Poll::Pending;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+/// Future returned by `TcpStream::connect`.
+pub struct ConnectFuture {
+    addr: SocketAddr,
+    // Socket of the connect in flight, registered with the reactor under `id`
+    stream: Option<mio::net::TcpStream>,
+    // Handed over to the `TcpStream` once connected
+    id: Option<usize>,
+}
+
+impl Future for ConnectFuture {
+    type Output = io::Result<TcpStream>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let id = this.id.expect("`ConnectFuture` polled after completion");
+        let stream = match this.stream.as_mut() {
+            Some(stream) => stream,
+            None => {
+                let mut stream = mio::net::TcpStream::connect(this.addr)?;
+                let interest = Interest::READABLE | Interest::WRITABLE;
+                reactor().register(&mut stream, interest, id)?;
+                this.stream.insert(stream)
+            }
+        };
+
+        // Stored before checking readiness so an event arriving in between
+        // isn't missed
+        reactor().set_waker(cx, id);
+        // Only a writable event means the handshake is done
+        if !reactor().take_readiness(id).writable {
+            reactor().check_running()?;
+            return Poll::Pending;
+        }
+
+        // A connect that failed reports its error through `SO_ERROR`
+        if let Some(e) = stream.take_error()? {
+            return Poll::Ready(Err(e));
+        }
+        match stream.peer_addr() {
+            Ok(_) => {}
+            // Woken before the handshake is done
+            Err(e) if e.kind() == ErrorKind::NotConnected => return Poll::Pending,
+            Err(e) => return Poll::Ready(Err(e)),
+        }
+
+        let stream = this.stream.take().unwrap();
+        this.id = None;
+        Poll::Ready(Ok(TcpStream::new(stream, id)))
+    }
+}
+
+impl Drop for ConnectFuture {
+    /// Abandons the connect in flight, if any, and releases the id unless
+    /// it was handed over to the connected `TcpStream`.
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+        if let Some(mut stream) = self.stream.take() {
+            let _ = reactor().deregister(&mut stream, id);
+        }
+        reactor().release_id(id);
+    }
+}
+
+/// Future returned by `TcpStream::read`.
+pub struct ReadFuture<'a> {
+    stream: &'a mut TcpStream,
+    buf: &'a mut [u8],
+}
+
+impl Future for ReadFuture<'_> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.stream.poll_io(cx, |stream| stream.read(this.buf))
+    }
+}
+
+/// Future returned by `TcpStream::write`.
+pub struct WriteFuture<'a> {
+    stream: &'a mut TcpStream,
+    buf: &'a [u8],
+}
+
+impl Future for WriteFuture<'_> {
+    type Output = io::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.stream.poll_write_buf(cx, this.buf)
+    }
+}
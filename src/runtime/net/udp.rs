@@ -0,0 +1,111 @@
+use crate::runtime::reactor;
+use mio::Interest;
+use std::{
+    future::Future,
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+// Largest payload a UDP datagram can carry
+const MAX_DATAGRAM: usize = 65_535;
+
+/// A UDP socket whose receives wait on the reactor instead of blocking.
+/// Sends are made right away: a datagram either fits in the socket's send
+/// buffer or the send fails with `WouldBlock`.
+pub struct UdpSocket {
+    socket: mio::net::UdpSocket,
+    id: usize,
+    // Registered with the reactor on the first receive that has to wait
+    registered: bool,
+}
+
+impl UdpSocket {
+    /// Binds a socket to `addr`. Port 0 lets the OS pick one.
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        Ok(Self {
+            socket: mio::net::UdpSocket::bind(addr)?,
+            id: reactor().next_id(),
+            registered: false,
+        })
+    }
+
+    /// Sets the default destination for `send`, and only accepts datagrams
+    /// from `addr` on `recv`.
+    pub fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        self.socket.connect(addr)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Sends `buf` as a single datagram to `target`.
+    pub fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        self.socket.send_to(buf, target)
+    }
+
+    /// Sends `buf` as a single datagram to the address set with `connect`.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.send(buf)
+    }
+
+    /// Resolves to the next datagram received and the address it came from.
+    pub fn recv_from(&mut self) -> UdpRecvFuture<'_> {
+        UdpRecvFuture { socket: self }
+    }
+
+    /// Resolves to the payload of the next datagram received.
+    pub fn recv(&mut self) -> impl Future<Output = io::Result<Vec<u8>>> + '_ {
+        let fut = self.recv_from();
+        async move { Ok(fut.await?.0) }
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        if self.registered {
+            let _ = reactor().deregister(&mut self.socket, self.id);
+        }
+        reactor().release_id(self.id);
+    }
+}
+
+/// Future returned by `UdpSocket::recv_from`.
+pub struct UdpRecvFuture<'a> {
+    socket: &'a mut UdpSocket,
+}
+
+impl Future for UdpRecvFuture<'_> {
+    type Output = io::Result<(Vec<u8>, SocketAddr)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let socket = &mut *self.socket;
+        let mut buf = vec![0u8; MAX_DATAGRAM];
+        loop {
+            match socket.socket.recv_from(&mut buf) {
+                Ok((n, from)) => {
+                    buf.truncate(n);
+                    return Poll::Ready(Ok((buf, from)));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    reactor().check_running()?;
+                    // The waker is stored first so the first event can't be missed
+                    reactor().set_waker(cx, socket.id);
+                    if !socket.registered {
+                        let (id, interest) = (socket.id, Interest::READABLE);
+                        if let Err(e) = reactor().register(&mut socket.socket, interest, id) {
+                            reactor().remove_waker(id);
+                            return Poll::Ready(Err(e));
+                        }
+                        socket.registered = true;
+                    }
+                    return Poll::Pending;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// Hooks into the executor's task lifecycle. Every method has an empty
+/// default implementation so an observer only needs to implement the
+/// callbacks it cares about. Observers are called on the executor thread,
+/// so they should return quickly.
+pub trait Observer {
+    /// Called right before the task with `id` is polled.
+    fn on_poll_begin(&self, _id: usize) {}
+
+    /// Called right after polling the task with `id` returned. `ready` tells
+    /// if the task finished (`Poll::Ready`) or will be polled again later.
+    fn on_poll_end(&self, _id: usize, _ready: bool) {}
+}
+
+/// Opens a `tracing` span for every poll of a task, tagged with the task id,
+/// so work done inside the poll (including nested async calls) can be
+/// correlated with the task it belongs to.
+#[cfg(feature = "tracing")]
+#[derive(Default)]
+pub struct TracingObserver {
+    // Span entered by `on_poll_begin` and not yet closed by `on_poll_end`.
+    // There's at most one, since a thread polls one task at a time and
+    // `block_on` can't be nested.
+    span: std::cell::RefCell<Option<tracing::span::EnteredSpan>>,
+}
+
+#[cfg(feature = "tracing")]
+impl Observer for TracingObserver {
+    fn on_poll_begin(&self, id: usize) {
+        let span = tracing::trace_span!("poll", task.id = id).entered();
+        *self.span.borrow_mut() = Some(span);
+    }
+
+    fn on_poll_end(&self, id: usize, ready: bool) {
+        tracing::trace!(task.id = id, ready, "poll finished");
+        self.span.borrow_mut().take();
+    }
+}
@@ -1,15 +1,28 @@
-use mio::{net::TcpStream, Events, Interest, Poll, Registry, Token};
+use mio::{
+    event::{Event, Source},
+    Events, Interest, Poll, Registry, Token,
+};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
+    io,
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
     },
     task::{Context, Waker},
-    thread,
+    thread::{self, JoinHandle},
+    time::Instant,
 };
 
-type Wakers = Arc<Mutex<HashMap<usize, Waker>>>;
+type Wakers = Arc<Mutex<HashMap<usize, Waiting>>>;
+type Readinesses = Arc<Mutex<HashMap<usize, Readiness>>>;
+// Ordered by deadline first so the nearest one is always at the front.
+type Timers = Arc<Mutex<BTreeSet<(Instant, usize)>>>;
+type Ids = Arc<Mutex<IdPool>>;
+
+// Token of the `mio::Waker` used to interrupt `Poll::poll`. Ids handed
+// out by `next_id` start at 1, so this never clashes with a registration.
+const WAKER_TOKEN: Token = Token(0);
 
 // Ensure that there can only be a single instance of this
 // specific `Reactor` running in our program.
@@ -19,27 +32,106 @@ pub fn reactor() -> &'static Reactor {
     REACTOR.get().expect("Called outside a runtime context")
 }
 
+/// Which kinds of readiness were reported for a registered source since
+/// they were last taken with `Reactor::take_readiness`. Errors and closed
+/// halves of a connection count as readiness too, since reading or writing
+/// is how the error gets reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    pub readable: bool,
+    pub writable: bool,
+}
+
+impl Readiness {
+    fn from_event(event: &Event) -> Self {
+        Self {
+            readable: event.is_readable() || event.is_read_closed() || event.is_error(),
+            writable: event.is_writable() || event.is_write_closed() || event.is_error(),
+        }
+    }
+
+    fn merge(&mut self, other: Readiness) {
+        self.readable |= other.readable;
+        self.writable |= other.writable;
+    }
+}
+
+/// A `Waker` stored with `Reactor::set_waker`, along with when it was stored.
+struct Waiting {
+    // Taken from `Reactor::next_seq`, so a lower one was stored earlier
+    seq: u64,
+    waker: Waker,
+}
+
+/// Ids given back with `Reactor::release_id`, reused by `next_id` so the
+/// maps keyed by id don't keep growing.
+#[derive(Default)]
+struct IdPool {
+    // Safe to hand out again
+    free: Vec<usize>,
+    // Released, but an event for the old owner might already be on its way
+    // through the event loop. Moved to `free` once a round of events has
+    // been handled since.
+    retired: Vec<usize>,
+}
+
+/// Locks `mutex`, recovering the data if a thread panicked while holding
+/// it. The maps and sets behind the reactor's mutexes are left consistent by
+/// every operation on them, so a panic elsewhere doesn't invalidate them.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 pub struct Reactor {
     /// Hashmap of Waker objects identified by usize
     wakers: Wakers,
+    /// Readiness reported for each id, shared with the event loop
+    readiness: Readinesses,
     /// Registry instance to interact with the event queue in mio
     registry: Registry,
     /// Tracks which event occured & which `Waker` should be woken
     next_id: AtomicUsize,
+    /// Orders the `Waker`s in `wakers` by when they were stored
+    next_seq: AtomicU64,
+    /// Number of sources currently registered, see `registrations`
+    registered: AtomicUsize,
+    /// Ids released for reuse, shared with the event loop
+    ids: Ids,
+    /// Deadlines set with `set_timeout`, shared with the event loop
+    timers: Timers,
+    /// Interrupts the event loop while it's blocked in `Poll::poll`
+    waker: mio::Waker,
+    /// Set by `shutdown`, shared with the event loop
+    shut_down: Arc<AtomicBool>,
+    /// Thread running the event loop, joined by `shutdown`
+    thread: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl Reactor {
 
     /// Wrapper around `Registry::register`. `id` property is passed to
     /// identify which event has occured when a notification is received later.
+    /// Takes any `Source`, e.g. a `TcpStream`, `TcpListener` or `UdpSocket`.
     pub fn register(
         &self,
-        stream: &mut TcpStream,
+        source: &mut impl Source,
+        interest: Interest,
+        id: usize,
+    ) -> io::Result<()> {
+        self.registry.register(source, Token(id), interest)?;
+        self.registered.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Wrapper around `Registry::reregister`, changing the readiness `id` is
+    /// woken on.
+    pub fn reregister(
+        &self,
+        source: &mut impl Source,
         interest: Interest,
-        id: usize
-    )
-    {
-        self.registry.register(stream, Token(id), interest).unwrap();
+        id: usize,
+    ) -> io::Result<()> {
+        self.registry.reregister(source, Token(id), interest)
     }
 
     /// Adds a `Waker` to the `HashMap` using the provided `id` property
@@ -47,68 +139,239 @@ impl Reactor {
     /// and the old one is dropped. The most recent `Waker` should always be
     /// the one stored so that this fn can be called multiple times, eventhough
     /// there is already a `Waker` associated with the `TcpStream`.
+    ///
+    /// When several ids fire at once they're woken in the order their
+    /// `Waker`s were stored, so the task that has been waiting the longest
+    /// is woken first. Together with `SchedulingPolicy::Fifo` it's also
+    /// polled first.
     pub fn set_waker(&self, cx: &Context, id: usize) {
-        let _ = self
-            .wakers
-            .lock()
-            .map(|mut w| w.insert(id, cx.waker().clone()).is_none())
-            .unwrap();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let waker = cx.waker().clone();
+        lock(&self.wakers).insert(id, Waiting { seq, waker });
+    }
+
+    /// Removes the `Waker` stored for `id`, if any, for ids that only ever
+    /// had a timer and no `Source` registered.
+    pub fn remove_waker(&self, id: usize) {
+        lock(&self.wakers).remove(&id);
+    }
+
+    /// Returns the readiness reported for `id` since the last call, and
+    /// resets it. Lets a task woken for a source registered with more than
+    /// one `Interest` find out whether it can read, write or both.
+    pub fn take_readiness(&self, id: usize) -> Readiness {
+        lock(&self.readiness).remove(&id).unwrap_or_default()
+    }
+
+    /// Removes the `Waker` from `wakers`, if there is one: a source can be
+    /// deregistered before `set_waker` was ever called for it, e.g. when its
+    /// connection fails before the first poll. Then, derigisters the
+    /// `Source` from the `Poll` instance.
+    pub fn deregister(&self, source: &mut impl Source, id: usize) -> io::Result<()> {
+        lock(&self.wakers).remove(&id);
+        lock(&self.readiness).remove(&id);
+        self.registry.deregister(source)?;
+        self.registered.fetch_sub(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// How many sources are registered right now, i.e. registered but not
+    /// deregistered yet. Everything in this crate deregisters its sources
+    /// when dropped, including futures dropped before completing, so once
+    /// they're gone this is back to where it was. Useful to check that
+    /// nothing leaks, e.g. after cancelling requests.
+    pub fn registrations(&self) -> usize {
+        self.registered.load(Ordering::Relaxed)
     }
 
-    /// Removes the `Waker` from `wakers`. Then, derigisters the
-    /// `TcpStream` from the `Poll` instance.
-    pub fn deregister(&self, stream: &mut TcpStream, id: usize) {
-        let _ = self.wakers.lock().map(|mut w| w.remove(&id).unwrap());
-        self.registry.deregister(stream).unwrap();
+    /// Asks the reactor to wake the `Waker` stored for `id` once `when` has
+    /// passed. The event loop might be blocked waiting for a later deadline
+    /// (or none at all), so it's woken up to recalculate its timeout. If that
+    /// fails the deadline is still set, but only picked up once the event loop
+    /// wakes up for another reason.
+    pub fn set_timeout(&self, when: Instant, id: usize) -> io::Result<()> {
+        lock(&self.timers).insert((when, id));
+        self.waker.wake()
     }
 
-    /// Gets the current `next_id` and increments the counter atomically.
+    /// Removes any deadline set for `id` that hasn't fired yet.
+    pub fn clear_timeout(&self, id: usize) {
+        lock(&self.timers).retain(|&(_, i)| i != id);
+    }
+
+    /// Stops the event loop and waits for its thread to exit. Every stored
+    /// `Waker` is woken one last time so tasks waiting on the reactor notice;
+    /// from then on `check_running` returns an error for them to fail with.
+    /// The reactor can't be started again afterwards.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.shut_down.store(true, Ordering::SeqCst);
+        self.waker.wake()?;
+        if let Some(thread) = lock(&self.thread).take() {
+            let _ = thread.join();
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the reactor has been shut down, meaning nothing
+    /// registered with it will ever be woken again.
+    pub fn check_running(&self) -> io::Result<()> {
+        if self.shut_down.load(Ordering::SeqCst) {
+            return Err(io::Error::other("reactor has been shut down"));
+        }
+        Ok(())
+    }
+
+    /// Hands out an id released earlier if there is one, otherwise gets
+    /// the current `next_id` and increments the counter atomically.
     /// We don't care about happens before/after relationships. We only care
     /// about not handing the same id twice, so `Ordering::Relaxed` is enough.
     pub fn next_id(&self) -> usize {
-        self.next_id.fetch_add(1, Ordering::Relaxed)
+        lock(&self.ids)
+            .free
+            .pop()
+            .unwrap_or_else(|| self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Gives `id` back for `next_id` to hand out again, once whatever used
+    /// it is done with it for good. Everything stored for `id` is removed,
+    /// but any `Source` registered under it must have been deregistered
+    /// first. The id is only reused after the event loop has handled another
+    /// round of events, so an event that was already on its way can't wake
+    /// the next owner. Releasing an id twice would hand it out twice.
+    pub fn release_id(&self, id: usize) {
+        lock(&self.wakers).remove(&id);
+        lock(&self.readiness).remove(&id);
+        self.clear_timeout(id);
+        lock(&self.ids).retired.push(id);
     }
 }
 
 /// 1. Create an `events` collection.
-/// 2. Loop indefinitely. Not ideal. No way to shut down event loop once started.
-/// 3. Call `Poll::poll` with a timeout of `None`, meaning it will never time out
-///    and block until it receives an event notification.
+/// 2. Loop until `Reactor::shutdown` is called.
+/// 3. Call `Poll::poll` with a timeout that expires at the nearest deadline set
+///    with `set_timeout`. Without any deadline it's `None`, meaning it will never
+///    time out and block until it receives an event notification. If polling
+///    fails for any reason other than being interrupted, the reactor shuts down
+///    the same way `Reactor::shutdown` does.
 /// 4. When the call returns, loop through every event received.
 /// 5. If an event is received it means something we registered interest in happened.
 ///    Get the `id` we passed when we first registered an interest in events on this
-///    `TcpStream`.
-/// 6. Try to get the associated `Waker` and call `Waker::wake` on it. Guard against
-///    the fact that `Waker` may have been removed from the collection already, in which
-///    case nothing is done.
-fn event_loop(mut poll: Poll, wakers: Wakers) {
+///    `TcpStream`. Events for `WAKER_TOKEN` only exist to interrupt `Poll::poll`
+///    when a new deadline is set or on shutdown. In the latter case every `Waker`
+///    is woken and the loop exits, otherwise they're skipped.
+/// 6. Record which readiness the event reports for `id`, to be picked up with
+///    `Reactor::take_readiness`, and remember that `id` fired.
+/// 7. Remove every deadline that has passed and remember its `id` the same way.
+///    Then wake the `Waker` of every `id` that fired, see `wake_in_order`.
+/// 8. Make the ids released with `release_id` until now available again. Any
+///    stale readiness recorded for them in step 6 is dropped.
+fn event_loop(
+    mut poll: Poll,
+    wakers: Wakers,
+    readiness: Readinesses,
+    timers: Timers,
+    ids: Ids,
+    shut_down: Arc<AtomicBool>,
+) {
     let mut events = Events::with_capacity(100);
     loop {
-        poll.poll(&mut events, None).unwrap();
+        let timeout = lock(&timers)
+            .first()
+            .map(|(when, _)| when.saturating_duration_since(Instant::now()));
+        match poll.poll(&mut events, timeout) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                // Nothing will be woken by this `Poll` anymore, so shut down
+                // and let every task find out through `check_running`.
+                log::error!("reactor: polling failed, shutting down: {e}");
+                shut_down.store(true, Ordering::SeqCst);
+                lock(&wakers).values().for_each(|w| w.waker.wake_by_ref());
+                return;
+            }
+        }
+
+        let mut fired = vec![];
         for e in events.iter() {
+            if e.token() == WAKER_TOKEN {
+                if shut_down.load(Ordering::SeqCst) {
+                    lock(&wakers).values().for_each(|w| w.waker.wake_by_ref());
+                    return;
+                }
+                continue;
+            }
+
             let Token(id) = e.token();
-            let wakers = wakers.lock().unwrap();
+            lock(&readiness)
+                .entry(id)
+                .or_default()
+                .merge(Readiness::from_event(e));
+            fired.push(id);
+        }
 
-            if let Some(waker) = wakers.get(&id) {
-                waker.wake_by_ref();
+        let now = Instant::now();
+        let mut timers = lock(&timers);
+        while let Some(&(when, id)) = timers.first() {
+            if when > now {
+                break;
             }
+            timers.pop_first();
+            fired.push(id);
+        }
+        drop(timers);
+        wake_in_order(&wakers, &fired);
+
+        let mut ids = lock(&ids);
+        let retired = std::mem::take(&mut ids.retired);
+        let mut readiness = lock(&readiness);
+        for id in &retired {
+            readiness.remove(id);
         }
+        ids.free.extend(retired);
     }
 }
 
-pub fn start() {
-    use thread::spawn;
+/// Wakes the `Waker` stored for each of `ids`, oldest first, so the order
+/// doesn't depend on the order the OS reported events in. Guards against the
+/// `Waker` having been removed from the collection already, in which case
+/// nothing is done. An id that fired more than once, e.g. for an event and a
+/// deadline, is only woken once.
+fn wake_in_order(wakers: &Wakers, ids: &[usize]) {
+    let wakers = lock(wakers);
+    let mut woken: Vec<&Waiting> = ids.iter().filter_map(|id| wakers.get(id)).collect();
+    woken.sort_by_key(|w| w.seq);
+    woken.dedup_by_key(|w| w.seq);
+    woken.iter().for_each(|w| w.waker.wake_by_ref());
+}
 
+pub fn start() {
     let wakers = Arc::new(Mutex::new(HashMap::new()));
+    let readiness = Arc::new(Mutex::new(HashMap::new()));
+    let timers = Arc::new(Mutex::new(BTreeSet::new()));
     let poll = Poll::new().unwrap();
     let registry = poll.registry().try_clone().unwrap();
+    let waker = mio::Waker::new(poll.registry(), WAKER_TOKEN).unwrap();
     let next_id = AtomicUsize::new(1);
+    let ids = Arc::new(Mutex::new(IdPool::default()));
+    let shut_down = Arc::new(AtomicBool::new(false));
     let reactor = Reactor {
         wakers: wakers.clone(),
+        readiness: readiness.clone(),
         registry,
         next_id,
+        next_seq: AtomicU64::new(0),
+        registered: AtomicUsize::new(0),
+        ids: ids.clone(),
+        timers: timers.clone(),
+        waker,
+        shut_down: shut_down.clone(),
+        thread: Mutex::new(None),
     };
 
     REACTOR.set(reactor).ok().expect("Reactor already running");
-    spawn(move || event_loop(poll, wakers));
+    let thread = thread::Builder::new()
+        .name("reactor".to_string())
+        .spawn(move || event_loop(poll, wakers, readiness, timers, ids, shut_down))
+        .expect("failed to spawn reactor thread");
+    *lock(&self::reactor().thread) = Some(thread);
 }
\ No newline at end of file
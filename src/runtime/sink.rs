@@ -0,0 +1,101 @@
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The write-side counterpart to a stream of values. A `Sink` accepts items
+/// one at a time and applies backpressure: `poll_ready` returns `Pending`
+/// until the sink has room for another item, so a fast producer is throttled
+/// by a slow consumer instead of buffering without bound.
+pub trait Sink<Item> {
+    type Error;
+
+    /// Checks if the sink can accept another item. When it can't, the `Waker`
+    /// in `cx` is stored and woken once space frees up.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>>;
+
+    /// Hands an item to the sink. Must only be called after `poll_ready`
+    /// returned `Ready(Ok(()))`.
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error>;
+
+    /// Flushes anything still buffered and closes the sink. No items can be
+    /// sent once this has returned `Ready`.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>>;
+}
+
+/// Convenience methods that turn the `poll_*` methods of a `Sink` into
+/// futures that can be `.await`ed.
+pub trait SinkExt<Item>: Sink<Item> {
+    /// Returns a future that resolves once `item` has been accepted by the sink.
+    fn send(&mut self, item: Item) -> SendFuture<'_, Self, Item>
+    where
+        Self: Unpin,
+    {
+        SendFuture {
+            sink: self,
+            item: Some(item),
+        }
+    }
+
+    /// Returns a future that resolves once the sink has been flushed and closed.
+    fn close(&mut self) -> CloseFuture<'_, Self, Item>
+    where
+        Self: Unpin,
+    {
+        CloseFuture {
+            sink: self,
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<S, Item> SinkExt<Item> for S where S: Sink<Item> + ?Sized {}
+
+pub struct SendFuture<'a, S: ?Sized, Item> {
+    sink: &'a mut S,
+    // `None` once the item has been handed over to the sink
+    item: Option<Item>,
+}
+
+impl<S: ?Sized, Item> Unpin for SendFuture<'_, S, Item> {}
+
+impl<S, Item> Future for SendFuture<'_, S, Item>
+where
+    S: Sink<Item> + Unpin + ?Sized,
+{
+    type Output = Result<(), S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        match Pin::new(&mut *this.sink).poll_ready(cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+
+        let item = this.item.take().expect("`SendFuture` polled after completion");
+        Poll::Ready(Pin::new(&mut *this.sink).start_send(item))
+    }
+}
+
+pub struct CloseFuture<'a, S: ?Sized, Item> {
+    sink: &'a mut S,
+    // `CloseFuture` has no item of its own but needs to know which `Sink<Item>`
+    // implementation to drive.
+    _item: PhantomData<fn(Item)>,
+}
+
+impl<S: ?Sized, Item> Unpin for CloseFuture<'_, S, Item> {}
+
+impl<S, Item> Future for CloseFuture<'_, S, Item>
+where
+    S: Sink<Item> + Unpin + ?Sized,
+{
+    type Output = Result<(), S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut *self.sink).poll_close(cx)
+    }
+}
@@ -0,0 +1,25 @@
+//! Primitives for coordinating tasks across `.await` points. Waiting on
+//! them makes the task return `Pending` instead of blocking the thread, so
+//! the executor can keep running the other tasks meanwhile.
+
+mod cancel;
+pub mod mpsc;
+mod mutex;
+mod notify;
+pub mod oneshot;
+mod semaphore;
+mod waiters;
+
+pub use cancel::{CancellationToken, Cancelled};
+pub use mutex::{Lock, Mutex, MutexGuard};
+pub use notify::{Notified, Notify};
+pub use semaphore::{Acquire, Permit, Semaphore};
+
+use std::sync::{MutexGuard as StdMutexGuard, PoisonError};
+
+/// Locks the std `mutex` guarding some primitive's state, recovering the
+/// data if a thread panicked while holding it. The state is only ever held
+/// locked briefly and left consistent, so a panic doesn't invalidate it.
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> StdMutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,125 @@
+use super::{lock, waiters::Waiters};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll},
+};
+
+/// Signals tasks to stop what they're doing. Clones share the same state,
+/// so cancelling any of them cancels them all. Tasks race their work
+/// against `cancelled` to bail out early.
+///
+/// Tokens made with `child_token` form a tree: cancelling a token cancels
+/// every token below it, but not the ones above.
+#[derive(Clone)]
+pub struct CancellationToken {
+    node: Arc<Mutex<Node>>,
+}
+
+struct Node {
+    cancelled: bool,
+    waiters: Waiters,
+    // Weak, so dropping every clone of a child token frees it
+    children: Vec<Weak<Mutex<Node>>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::with_cancelled(false)
+    }
+
+    fn with_cancelled(cancelled: bool) -> Self {
+        Self {
+            node: Arc::new(Mutex::new(Node {
+                cancelled,
+                waiters: Waiters::default(),
+                children: vec![],
+            })),
+        }
+    }
+
+    /// Returns a new token that's cancelled along with this one, or right
+    /// away if this one already is. Cancelling it leaves this one alone.
+    pub fn child_token(&self) -> CancellationToken {
+        let mut node = lock(&self.node);
+        if node.cancelled {
+            return Self::with_cancelled(true);
+        }
+        let child = Self::new();
+        node.children.retain(|child| child.strong_count() > 0);
+        node.children.push(Arc::downgrade(&child.node));
+        child
+    }
+
+    /// Cancels this token and every token below it, waking every task
+    /// waiting on `cancelled`. Cancelling again does nothing.
+    pub fn cancel(&self) {
+        cancel(&self.node);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        lock(&self.node).cancelled
+    }
+
+    /// Completes once the token is cancelled, or right away if it already is.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            id: None,
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cancels `node` and then its children, one at a time, so no two nodes'
+/// locks are ever held at once.
+fn cancel(node: &Mutex<Node>) {
+    let children = {
+        let mut node = lock(node);
+        if node.cancelled {
+            return;
+        }
+        node.cancelled = true;
+        node.waiters.wake_all();
+        std::mem::take(&mut node.children)
+    };
+    for child in children.iter().filter_map(Weak::upgrade) {
+        cancel(&child);
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`.
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    // Set once the task has been queued to wait
+    id: Option<usize>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let token = self.token;
+        let mut node = lock(&token.node);
+        if node.cancelled {
+            self.id = None;
+            return Poll::Ready(());
+        }
+        node.waiters.register(&mut self.id, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            lock(&self.token.node).waiters.remove(id);
+        }
+    }
+}
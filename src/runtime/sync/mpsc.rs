@@ -0,0 +1,249 @@
+//! A channel for streaming values from any number of tasks to one.
+
+use super::{lock, waiters::Waiters};
+use crate::runtime::Sink;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// The `Receiver` was dropped, so the value couldn't be sent. Holds the
+/// value that was being sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiver dropped")
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {}
+
+/// Why `Sender::try_send` couldn't queue a value. Both hold the value that
+/// was being sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is bounded and there's no room right now.
+    Full(T),
+    /// The `Receiver` was dropped.
+    Closed(T),
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "channel full"),
+            TrySendError::Closed(_) => write!(f, "receiver dropped"),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for TrySendError<T> {}
+
+struct Inner<T> {
+    queue: VecDeque<T>,
+    // Number of `Sender`s still around
+    senders: usize,
+    receiver_dropped: bool,
+    // `Waker` of the task waiting in `Receiver::recv`
+    waker: Option<Waker>,
+    // Most values `queue` may hold, `None` if it's unbounded
+    capacity: Option<usize>,
+    // Senders waiting for room in a full queue
+    blocked: Waiters,
+}
+
+impl<T> Inner<T> {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.queue.len() >= capacity)
+    }
+
+    fn push(&mut self, value: T) {
+        self.queue.push_back(value);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Sends values to the `Receiver`. Clone it to send from more tasks.
+///
+/// Sending is done through `Sink`, so `SinkExt::send` waits for room in a
+/// bounded channel: a producer can't get further ahead of the consumer than
+/// the capacity allows. `try_send` doesn't wait.
+pub struct Sender<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+    // Set once this sender has been queued to wait for room
+    waiter: Option<usize>,
+}
+
+/// Receives the values sent by every `Sender`, in the order they were sent.
+pub struct Receiver<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+/// Creates a `Sender` and a `Receiver` connected through a queue holding at
+/// most `capacity` values. Once it's full, senders wait until the `Receiver`
+/// takes a value out. Panics if `capacity` is 0.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "a channel needs room for at least one value");
+    with_capacity(Some(capacity))
+}
+
+/// Creates a `Sender` and a `Receiver` connected through an unbounded
+/// queue, so sending never has to wait.
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    with_capacity(None)
+}
+
+fn with_capacity<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Mutex::new(Inner {
+        queue: VecDeque::new(),
+        senders: 1,
+        receiver_dropped: false,
+        waker: None,
+        capacity,
+        blocked: Waiters::default(),
+    }));
+    let sender = Sender {
+        inner: inner.clone(),
+        waiter: None,
+    };
+    (sender, Receiver { inner })
+}
+
+impl<T> Sender<T> {
+    /// Queues `value` if there's room for it right away, and wakes the task
+    /// waiting on the `Receiver`. There's no room while senders are waiting
+    /// in `SinkExt::send`, so this doesn't jump the line.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut inner = lock(&self.inner);
+        if inner.receiver_dropped {
+            return Err(TrySendError::Closed(value));
+        }
+        if inner.is_full() || !inner.blocked.is_empty() {
+            return Err(TrySendError::Full(value));
+        }
+        inner.push(value);
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        lock(&self.inner).senders += 1;
+        Self {
+            inner: self.inner.clone(),
+            waiter: None,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    /// Dropping the last `Sender` wakes the `Receiver` so it can see that
+    /// nothing more is coming. A sender that was woken to use a free slot
+    /// passes the wakeup on, so the slot doesn't sit unused while others
+    /// wait.
+    fn drop(&mut self) {
+        let mut inner = lock(&self.inner);
+        if let Some(id) = self.waiter {
+            if !inner.blocked.remove(id) && !inner.is_full() {
+                inner.blocked.wake_one();
+            }
+        }
+        inner.senders -= 1;
+        if inner.senders == 0 {
+            if let Some(waker) = inner.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    /// Ready once the queue has room and every sender that started waiting
+    /// earlier got theirs. Also ready once the `Receiver` is dropped, since
+    /// there's nothing left to wait for; `start_send` then hands the value
+    /// back.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let mut inner = lock(&this.inner);
+        // A free slot is for whoever was woken to take it before anyone
+        // still waiting, and only then for newcomers
+        let first_in_line = match this.waiter {
+            Some(id) => !inner.blocked.contains(id),
+            None => inner.blocked.is_empty(),
+        };
+        if inner.receiver_dropped || (!inner.is_full() && first_in_line) {
+            this.waiter = None;
+            return Poll::Ready(Ok(()));
+        }
+        inner.blocked.register(&mut this.waiter, cx.waker());
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let mut inner = lock(&self.inner);
+        if inner.receiver_dropped {
+            return Err(SendError(item));
+        }
+        inner.push(item);
+        Ok(())
+    }
+
+    /// Nothing is buffered on the sending side. The channel itself only
+    /// closes once every `Sender` has been dropped.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Resolves to the next value, or to `None` once every `Sender` has
+    /// been dropped and all values sent before that have been received.
+    pub fn recv(&mut self) -> Recv<'_, T> {
+        Recv { receiver: self }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    /// Wakes every sender waiting for room, so they find out nothing will
+    /// be received anymore.
+    fn drop(&mut self) {
+        let mut inner = lock(&self.inner);
+        inner.receiver_dropped = true;
+        inner.blocked.wake_all();
+    }
+}
+
+/// Future returned by `Receiver::recv`.
+pub struct Recv<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut inner = lock(&self.receiver.inner);
+        if let Some(value) = inner.queue.pop_front() {
+            // That freed a slot for the sender that has waited longest
+            inner.blocked.wake_one();
+            return Poll::Ready(Some(value));
+        }
+        if inner.senders == 0 {
+            return Poll::Ready(None);
+        }
+        inner.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,128 @@
+use super::{lock, waiters::Waiters};
+use std::{
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard},
+    task::{Context, Poll},
+};
+
+/// A mutex that can be held across `.await` points. A task waiting for
+/// the lock is parked until the guard is dropped, instead of blocking the
+/// thread and with it every other task on the executor.
+pub struct Mutex<T> {
+    // Only ever locked by whoever holds the async lock, so it never blocks
+    value: StdMutex<T>,
+    state: StdMutex<State>,
+}
+
+struct State {
+    locked: bool,
+    waiters: Waiters,
+}
+
+impl<T> Mutex<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value: StdMutex::new(value),
+            state: StdMutex::new(State {
+                locked: false,
+                waiters: Waiters::default(),
+            }),
+        }
+    }
+
+    /// Resolves to a guard giving access to the value, right away if the
+    /// mutex isn't locked. Otherwise the task waits its turn behind the
+    /// tasks that started waiting earlier.
+    pub fn lock(&self) -> Lock<'_, T> {
+        Lock {
+            mutex: self,
+            id: None,
+        }
+    }
+
+    /// Consumes the mutex, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Future returned by `Mutex::lock`.
+pub struct Lock<'a, T> {
+    mutex: &'a Mutex<T>,
+    // Set once the task has been queued to wait for the lock
+    id: Option<usize>,
+}
+
+impl<'a, T> Future for Lock<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mutex = self.mutex;
+        let mut state = lock(&mutex.state);
+        if state.locked {
+            state.waiters.register(&mut self.id, cx.waker());
+            return Poll::Pending;
+        }
+
+        state.locked = true;
+        if let Some(id) = self.id.take() {
+            state.waiters.remove(id);
+        }
+        drop(state);
+        Poll::Ready(MutexGuard {
+            mutex,
+            guard: Some(lock(&mutex.value)),
+        })
+    }
+}
+
+impl<T> Drop for Lock<'_, T> {
+    /// A task woken to take the lock that's dropped instead passes the
+    /// wakeup on, so the next waiter doesn't wait forever.
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = lock(&self.mutex.state);
+            if !state.waiters.remove(id) && !state.locked {
+                state.waiters.wake_one();
+            }
+        }
+    }
+}
+
+/// Gives access to the value of a locked `Mutex`, and unlocks it when
+/// dropped. Like std's guard it can't be sent to another thread, so a task
+/// holding it across an `.await` can't run on a `MultiThreadExecutor`.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    // Only `None` while being dropped
+    guard: Option<StdMutexGuard<'a, T>>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    /// Unlocks the mutex and wakes the task that has waited longest for it.
+    fn drop(&mut self) {
+        // Released first so the woken task never finds it still locked
+        self.guard = None;
+        let mut state = lock(&self.mutex.state);
+        state.locked = false;
+        state.waiters.wake_one();
+    }
+}
@@ -0,0 +1,101 @@
+use super::{lock, waiters::Waiters};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+/// Lets tasks wait for an event that carries no value. `notify_one` that
+/// finds no task waiting is remembered, so the next task to wait doesn't
+/// miss it.
+pub struct Notify {
+    state: Mutex<State>,
+}
+
+struct State {
+    // Set by `notify_one` when no task was waiting
+    permit: bool,
+    waiters: Waiters,
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                permit: false,
+                waiters: Waiters::default(),
+            }),
+        }
+    }
+
+    /// Completes once the task is woken by `notify_one` or `notify_all`, or
+    /// right away if `notify_one` was called while no task was waiting. A
+    /// task woken by `notify_one` that drops the future before polling it
+    /// again takes the notification with it.
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            id: None,
+        }
+    }
+
+    /// Wakes the task that has waited longest. With no task waiting, the
+    /// next call to `notified` completes right away instead.
+    pub fn notify_one(&self) {
+        let mut state = lock(&self.state);
+        if !state.waiters.wake_one() {
+            state.permit = true;
+        }
+    }
+
+    /// Wakes every task waiting right now. Unlike `notify_one`, nothing is
+    /// remembered for tasks that start waiting later.
+    pub fn notify_all(&self) {
+        lock(&self.state).waiters.wake_all();
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Future returned by `Notify::notified`.
+pub struct Notified<'a> {
+    notify: &'a Notify,
+    // Set once the task has been queued to wait
+    id: Option<usize>,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let notify = self.notify;
+        let mut state = lock(&notify.state);
+        match self.id {
+            // Woken, since being woken is what takes it off the queue
+            Some(id) if !state.waiters.contains(id) => {
+                self.id = None;
+                return Poll::Ready(());
+            }
+            None if state.permit => {
+                state.permit = false;
+                return Poll::Ready(());
+            }
+            _ => {}
+        }
+        state.waiters.register(&mut self.id, cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            lock(&self.notify.state).waiters.remove(id);
+        }
+    }
+}
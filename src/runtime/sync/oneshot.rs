@@ -0,0 +1,102 @@
+//! A channel for sending a single value from one task to another.
+
+use super::lock;
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// The `Sender` was dropped without sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sender dropped without sending a value")
+    }
+}
+
+impl Error for RecvError {}
+
+struct Inner<T> {
+    value: Option<T>,
+    // Set whether or not the `Sender` sent a value first
+    sender_dropped: bool,
+    receiver_dropped: bool,
+    // `Waker` of the task awaiting the `Receiver`
+    waker: Option<Waker>,
+}
+
+/// Sends the value. Can be moved to another task.
+pub struct Sender<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+/// Resolves to the value once it's sent, or to `RecvError` if the `Sender`
+/// is dropped first.
+pub struct Receiver<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+/// Creates a connected `Sender` and `Receiver` pair.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Mutex::new(Inner {
+        value: None,
+        sender_dropped: false,
+        receiver_dropped: false,
+        waker: None,
+    }));
+    let sender = Sender {
+        inner: inner.clone(),
+    };
+    (sender, Receiver { inner })
+}
+
+impl<T> Sender<T> {
+    /// Hands `value` to the `Receiver` and wakes the task awaiting it. If
+    /// the `Receiver` has been dropped, `value` is given back instead.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut inner = lock(&self.inner);
+        if inner.receiver_dropped {
+            return Err(value);
+        }
+        inner.value = Some(value);
+        // The rest is done by `drop`, once `inner` is unlocked
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    /// Lets the `Receiver` know there's nothing more coming, which wakes it
+    /// with the value if one was sent and with `RecvError` otherwise.
+    fn drop(&mut self) {
+        let mut inner = lock(&self.inner);
+        inner.sender_dropped = true;
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        lock(&self.inner).receiver_dropped = true;
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut inner = lock(&self.inner);
+        if !inner.sender_dropped {
+            inner.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        Poll::Ready(inner.value.take().ok_or(RecvError))
+    }
+}
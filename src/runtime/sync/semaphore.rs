@@ -0,0 +1,105 @@
+use super::{lock, waiters::Waiters};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+/// Hands out a fixed number of permits, e.g. to bound how many requests
+/// are in flight at once. Tasks that find none left wait in line and get
+/// them in the order they asked.
+pub struct Semaphore {
+    state: Mutex<State>,
+}
+
+struct State {
+    permits: usize,
+    waiters: Waiters,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                permits,
+                waiters: Waiters::default(),
+            }),
+        }
+    }
+
+    /// Resolves to a `Permit` once one is free and every task that started
+    /// waiting earlier got theirs.
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            id: None,
+        }
+    }
+
+    /// Number of permits that aren't held right now.
+    pub fn available_permits(&self) -> usize {
+        lock(&self.state).permits
+    }
+
+    fn release(&self) {
+        let mut state = lock(&self.state);
+        state.permits += 1;
+        state.waiters.wake_one();
+    }
+}
+
+/// Future returned by `Semaphore::acquire`.
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    // Set once the task has been queued to wait for a permit
+    id: Option<usize>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = Permit<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let mut state = lock(&semaphore.state);
+        // Free permits are for whoever was woken to take them before
+        // anyone still waiting, and only then for newcomers
+        let first_in_line = match self.id {
+            Some(id) => !state.waiters.contains(id),
+            None => state.waiters.is_empty(),
+        };
+        if state.permits == 0 || !first_in_line {
+            state.waiters.register(&mut self.id, cx.waker());
+            return Poll::Pending;
+        }
+
+        state.permits -= 1;
+        self.id = None;
+        Poll::Ready(Permit { semaphore })
+    }
+}
+
+impl Drop for Acquire<'_> {
+    /// A task woken to take a permit that's dropped instead passes the
+    /// wakeup on, so the permit doesn't sit unused while others wait.
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = lock(&self.semaphore.state);
+            if !state.waiters.remove(id) && state.permits > 0 {
+                state.waiters.wake_one();
+            }
+        }
+    }
+}
+
+/// A permit from a `Semaphore`, given back when dropped.
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for Permit<'_> {
+    /// Returns the permit and wakes the task that has waited longest for one.
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
@@ -0,0 +1,64 @@
+use std::{collections::VecDeque, task::Waker};
+
+/// Queue of tasks waiting on a sync primitive, woken in the order they
+/// started waiting. Each waiting future gets an id the first time it's
+/// queued, so it can update its `Waker` or leave the queue when dropped.
+#[derive(Default)]
+pub(super) struct Waiters {
+    queue: VecDeque<(usize, Waker)>,
+    next_id: usize,
+}
+
+impl Waiters {
+    /// Queues the waiter `id` with `waker`, giving it an id first if it
+    /// doesn't have one yet. A waiter that's already queued just has its
+    /// `Waker` replaced. One that was woken but has to wait again, e.g.
+    /// because another task got there first, goes back to the front.
+    pub(super) fn register(&mut self, id: &mut Option<usize>, waker: &Waker) {
+        let Some(id) = *id else {
+            let new = self.next_id;
+            self.next_id += 1;
+            *id = Some(new);
+            self.queue.push_back((new, waker.clone()));
+            return;
+        };
+        match self.queue.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, stored)) => stored.clone_from(waker),
+            None => self.queue.push_front((id, waker.clone())),
+        }
+    }
+
+    /// Removes the waiter `id` and returns `true` if it was still queued,
+    /// or `false` if it had been woken already.
+    pub(super) fn remove(&mut self, id: usize) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|(i, _)| *i != id);
+        self.queue.len() != len
+    }
+
+    /// Returns `true` if the waiter `id` is queued and hasn't been woken yet.
+    pub(super) fn contains(&self, id: usize) -> bool {
+        self.queue.iter().any(|(i, _)| *i == id)
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Wakes the waiter that has waited longest, and returns `false` if
+    /// there was none.
+    pub(super) fn wake_one(&mut self) -> bool {
+        match self.queue.pop_front() {
+            Some((_, waker)) => {
+                waker.wake();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wakes every waiter queued right now.
+    pub(super) fn wake_all(&mut self) {
+        self.queue.drain(..).for_each(|(_, waker)| waker.wake());
+    }
+}
@@ -0,0 +1,122 @@
+use super::reactor;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Completes once `duration` has passed, counted from this call. The wait
+/// is driven by the reactor's timers, so the task isn't polled meanwhile.
+/// If the reactor is shut down first, it completes right away since the
+/// timer would never fire.
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(Instant::now() + duration)
+}
+
+/// Completes once `deadline` has passed, or on the first poll if it
+/// already has. Tasks sleeping until the same `deadline` are woken together.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep { deadline, id: None }
+}
+
+/// Future returned by `sleep` and `sleep_until`.
+pub struct Sleep {
+    deadline: Instant,
+    // Reactor id the timer is set under, once polled
+    id: Option<usize>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if Instant::now() >= self.deadline || reactor().check_running().is_err() {
+            return Poll::Ready(());
+        }
+
+        match self.id {
+            // Keep the most recent waker, the task might have moved
+            Some(id) => reactor().set_waker(cx, id),
+            None => {
+                let id = reactor().next_id();
+                self.id = Some(id);
+                // The waker has to be in place before the timer can fire
+                reactor().set_waker(cx, id);
+                // Even if waking the event loop fails the deadline is set, and
+                // there's no error to complete with, so there's nothing to do
+                let _ = reactor().set_timeout(self.deadline, id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    /// Removes the timer and its waker from the reactor, which matters when
+    /// the sleep is dropped before it has completed, and releases the id.
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            reactor().release_id(id);
+        }
+    }
+}
+
+/// Ticks every `period`, starting right away. Each deadline is counted
+/// from the previous one rather than from when `tick` was awaited, so
+/// ticks don't drift. Ticks missed because the task was busy for more than a
+/// period aren't made up for: the next tick completes right away and the
+/// ones after it are counted from then.
+///
+/// Panics if `period` is zero.
+pub fn interval(period: Duration) -> Interval {
+    assert!(!period.is_zero(), "`interval` period must be non-zero");
+    Interval {
+        period,
+        next: Instant::now(),
+        sleep: None,
+    }
+}
+
+/// Timer returned by `interval`.
+pub struct Interval {
+    period: Duration,
+    // Deadline of the next tick
+    next: Instant,
+    // Waits for `next`. Kept here rather than in `Tick` so a dropped `Tick`
+    // doesn't lose the timer it already set.
+    sleep: Option<Sleep>,
+}
+
+impl Interval {
+    /// Completes at the next tick.
+    pub fn tick(&mut self) -> Tick<'_> {
+        Tick { interval: self }
+    }
+}
+
+/// Future returned by `Interval::tick`.
+pub struct Tick<'a> {
+    interval: &'a mut Interval,
+}
+
+impl Future for Tick<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let interval = &mut *self.interval;
+        let deadline = interval.next;
+        let sleep = interval.sleep.get_or_insert_with(|| sleep_until(deadline));
+        if Pin::new(sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        interval.sleep = None;
+        let now = Instant::now();
+        interval.next = deadline + interval.period;
+        if interval.next <= now {
+            interval.next = now + interval.period;
+        }
+        Poll::Ready(())
+    }
+}
@@ -0,0 +1,39 @@
+use super::executor;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Yields to the executor once, letting other ready tasks run before the
+/// current one continues. Useful in loops that would otherwise keep the
+/// executor busy without ever returning `Pending`.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// Future returned by `yield_now`.
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    /// On the first poll the task's id is put back on the ready queue
+    /// behind every other ready task and `Pending` is returned. It completes
+    /// when polled again.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        // Outside this thread's executor, e.g. on a `MultiThreadExecutor`,
+        // waking the task queues it just the same, only without control
+        // over which end of the queue it goes on.
+        if !executor::defer_current(cx.waker()) {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
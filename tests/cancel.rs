@@ -0,0 +1,47 @@
+//! The reactor's registration count is shared by the whole process, so this
+//! file holds a single test.
+
+mod common;
+
+use a_runtime::{
+    future,
+    http::HttpClient,
+    runtime::{self, reactor, SchedulingPolicy},
+};
+use std::{net::TcpListener, thread, time::Duration};
+
+#[test]
+fn cancelled_requests_leave_no_registrations() {
+    // Accepts connections but never responds, so every request is still in
+    // flight when its timeout fires
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+
+    let mut executor = common::executor(SchedulingPolicy::default());
+    let baseline = reactor().registrations();
+    executor.block_on(async move {
+        let client = HttpClient::new(&addr);
+        for _ in 0..5 {
+            let result = future::timeout(Duration::from_millis(20), client.get("/")).await;
+            assert!(result.is_err(), "{result:?}");
+            assert_eq!(reactor().registrations(), baseline);
+        }
+
+        // Make sure there was something to clean up at all
+        let get = runtime::spawn(client.get("/"));
+        runtime::sleep(Duration::from_millis(20)).await;
+        assert_eq!(reactor().registrations(), baseline + 1);
+        get.abort();
+        assert!(get.await.is_err());
+        assert_eq!(reactor().registrations(), baseline);
+
+        let gets = (0..5).map(|_| client.get("/")).collect();
+        let result = future::timeout(Duration::from_millis(20), future::join_all(gets)).await;
+        assert!(result.is_err());
+    });
+
+    assert_eq!(reactor().registrations(), baseline);
+}
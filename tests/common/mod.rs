@@ -0,0 +1,82 @@
+//! Helpers shared by the integration tests. Each file in `tests/` is its own
+//! process, but its tests run on several threads of it.
+
+#![allow(dead_code)]
+
+use a_runtime::runtime::{self, Executor, SchedulingPolicy};
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener},
+    sync::Once,
+    thread,
+};
+
+/// Returns an executor for the current thread, starting the reactor first
+/// if no test in this process has yet. It can only be started once.
+pub fn executor(policy: SchedulingPolicy) -> Executor {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        runtime::init(policy);
+    });
+    let mut executor = Executor::new();
+    executor.set_policy(policy);
+    executor
+}
+
+/// Answers every connection made to the returned address with what
+/// `respond` makes of the request head, and then closes it.
+pub fn serve(respond: impl Fn(&[u8]) -> Vec<u8> + Send + 'static) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let head = read_head(&mut stream);
+            let _ = stream.write_all(&respond(&head));
+        }
+    });
+    addr
+}
+
+/// Like `serve`, but leaves every connection open once it has responded,
+/// so a response can only end where its framing says it does.
+pub fn serve_open(respond: impl Fn(&[u8]) -> Vec<u8> + Send + 'static) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut open = vec![];
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let head = read_head(&mut stream);
+            let _ = stream.write_all(&respond(&head));
+            open.push(stream);
+        }
+    });
+    addr
+}
+
+/// Reads from `stream` until the blank line ending a request head, and then
+/// skips the body its `Content-Length` announces, so closing the stream
+/// afterwards doesn't reset the connection over unread bytes.
+pub fn read_head(stream: &mut impl Read) -> Vec<u8> {
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut byte) {
+            Ok(1) => head.push(byte[0]),
+            _ => break,
+        }
+    }
+    let length = String::from_utf8_lossy(&head)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    let _ = io::copy(&mut stream.take(length), &mut io::sink());
+    head
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use a_runtime::{http::HttpClient, runtime::SchedulingPolicy};
+use std::time::Duration;
+
+#[test]
+fn host_name_is_resolved() {
+    let addr = common::serve(|_| b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_vec());
+    let client = HttpClient::new(&format!("localhost:{}", addr.port()));
+
+    let text = common::executor(SchedulingPolicy::default())
+        .block_on(client.get_timeout("/", Duration::from_secs(5)))
+        .unwrap();
+
+    assert_eq!(text, "ok");
+}
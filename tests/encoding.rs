@@ -0,0 +1,186 @@
+#![cfg(feature = "gzip")]
+
+mod common;
+
+use a_runtime::{
+    http::{HttpClient, HttpError, Response},
+    runtime::SchedulingPolicy,
+};
+use flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use std::{io::Write, net::SocketAddr, time::Duration};
+
+const BODY: &str = "the same line over and over again\n";
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// A server that sends `body` with `Content-Encoding: encoding`, whatever
+/// was asked for, and echoes the request's `Accept-Encoding` back.
+fn serve_encoded(encoding: &'static str, body: Vec<u8>) -> SocketAddr {
+    common::serve(move |head| {
+        let head = String::from_utf8_lossy(head);
+        let accept = head
+            .lines()
+            .find_map(|line| line.strip_prefix("Accept-Encoding: "))
+            .unwrap_or("none");
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\n\
+             Content-Encoding: {encoding}\r\n\
+             Content-Length: {}\r\n\
+             X-Accept-Encoding: {accept}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(&body);
+        response
+    })
+}
+
+/// The whole body in chunks of at most 7 bytes, so compressed data is split
+/// at arbitrary points.
+fn chunked(body: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    for chunk in body.chunks(7) {
+        out.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(b"0\r\n\r\n");
+    out
+}
+
+#[test]
+fn gzip_body_round_trips() {
+    let body = BODY.repeat(100);
+    let addr = serve_encoded("gzip", gzip(body.as_bytes()));
+
+    let response = common::executor(SchedulingPolicy::default())
+        .block_on(
+            HttpClient::new(&addr.to_string())
+                .auto_decompress(true)
+                .get_response("/"),
+        )
+        .unwrap();
+
+    assert_eq!(response.text().unwrap(), body);
+    assert_eq!(response.header("x-accept-encoding"), Some("gzip, deflate"));
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+}
+
+#[test]
+fn chunked_deflate_body_round_trips() {
+    let body = BODY.repeat(100);
+    let compressed = deflate(body.as_bytes());
+    let addr = common::serve(move |_| {
+        let mut response = b"HTTP/1.1 200 OK\r\n\
+            Content-Encoding: deflate\r\n\
+            Transfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+        response.extend_from_slice(&chunked(&compressed));
+        response
+    });
+
+    let text = common::executor(SchedulingPolicy::default())
+        .block_on(
+            HttpClient::new(&addr.to_string())
+                .auto_decompress(true)
+                .get("/"),
+        )
+        .unwrap();
+
+    assert_eq!(text, body);
+}
+
+#[test]
+fn unsupported_encoding_fails() {
+    let addr = serve_encoded("br", b"not really brotli".to_vec());
+
+    let result = common::executor(SchedulingPolicy::default()).block_on(
+        HttpClient::new(&addr.to_string())
+            .auto_decompress(true)
+            .get("/"),
+    );
+
+    match result {
+        Err(HttpError::UnsupportedEncoding(encoding)) => assert_eq!(encoding, "br"),
+        other => panic!("expected an unsupported encoding, got {other:?}"),
+    }
+}
+
+#[test]
+fn auto_decompress_is_off_by_default() {
+    let compressed = gzip(BODY.as_bytes());
+    let addr = serve_encoded("gzip", compressed.clone());
+    let client = HttpClient::new(&addr.to_string());
+
+    let response = common::executor(SchedulingPolicy::default())
+        .block_on(client.get_response("/"))
+        .unwrap();
+
+    assert_eq!(response.body, compressed);
+    assert_eq!(response.header("x-accept-encoding"), Some("none"));
+}
+
+#[test]
+fn own_accept_encoding_keeps_body_as_sent() {
+    let compressed = gzip(BODY.as_bytes());
+    let addr = serve_encoded("gzip", compressed.clone());
+    let request = HttpClient::new(&addr.to_string())
+        .auto_decompress(true)
+        .request("GET", "/")
+        .header("Accept-Encoding", "identity");
+
+    let response = common::executor(SchedulingPolicy::default())
+        .block_on(request.send_response())
+        .unwrap();
+
+    assert_eq!(response.body, compressed);
+    assert_eq!(response.header("x-accept-encoding"), Some("identity"));
+}
+
+/// Sends a `method` request to a server that answers with `head`, claiming a
+/// gzip body it doesn't send, and keeps the connection open.
+fn send_bodiless(method: &str, head: &'static str) -> Result<Response, HttpError> {
+    let addr = common::serve_open(move |_| head.as_bytes().to_vec()).to_string();
+    let request = HttpClient::new(&addr)
+        .auto_decompress(true)
+        .request(method, "/")
+        .timeout(Duration::from_secs(2));
+    common::executor(SchedulingPolicy::default()).block_on(request.send_response())
+}
+
+#[test]
+fn head_response_with_gzip_headers_has_no_body() {
+    let response = send_bodiless(
+        "HEAD",
+        "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 120\r\n\r\n",
+    )
+    .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn not_modified_with_gzip_headers_has_no_body() {
+    let response = send_bodiless(
+        "GET",
+        "HTTP/1.1 304 Not Modified\r\nContent-Encoding: gzip\r\nContent-Length: 120\r\n\r\n",
+    )
+    .unwrap();
+
+    assert_eq!(response.status, 304);
+    assert!(response.body.is_empty());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use a_runtime::{
+    future,
+    http::{HttpClient, HttpError},
+    runtime::{self, JoinError, SchedulingPolicy},
+    RuntimeError,
+};
+use std::{net::TcpListener, thread, time::Duration};
+
+/// Address nothing listens on, so connecting to it is refused.
+fn closed_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// Address of a server that accepts connections but never responds.
+fn silent_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+    addr
+}
+
+#[test]
+fn connect_failure_is_http_connect() {
+    let addr = closed_addr();
+    let result: Result<String, RuntimeError> = common::executor(SchedulingPolicy::default())
+        .block_on(async move { Ok(HttpClient::new(&addr).get("/").await?) });
+
+    let error = result.unwrap_err();
+    assert!(
+        matches!(error, RuntimeError::Http(HttpError::Connect(_))),
+        "{error:?}"
+    );
+    assert!(!error.is_timeout());
+}
+
+#[test]
+fn request_timeout_is_http_timeout() {
+    let addr = silent_addr();
+    let result: Result<String, RuntimeError> = common::executor(SchedulingPolicy::default())
+        .block_on(async move {
+            let client = HttpClient::new(&addr);
+            Ok(client.get_timeout("/", Duration::from_millis(50)).await?)
+        });
+
+    let error = result.unwrap_err();
+    assert!(
+        matches!(error, RuntimeError::Http(HttpError::Timeout)),
+        "{error:?}"
+    );
+    assert!(error.is_timeout());
+}
+
+#[test]
+fn future_timeout_is_timeout() {
+    let addr = silent_addr();
+    let result: Result<String, RuntimeError> = common::executor(SchedulingPolicy::default())
+        .block_on(async move {
+            let get = HttpClient::new(&addr).get("/");
+            Ok(future::timeout(Duration::from_millis(50), get).await??)
+        });
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, RuntimeError::Timeout(_)), "{error:?}");
+    assert!(error.is_timeout());
+}
+
+#[test]
+fn cancellation_is_join_cancelled() {
+    let result: Result<(), RuntimeError> =
+        common::executor(SchedulingPolicy::default()).block_on(async {
+            let handle = runtime::spawn(future::pending::<()>());
+            handle.abort();
+            Ok(handle.await?)
+        });
+
+    let error = result.unwrap_err();
+    assert!(
+        matches!(error, RuntimeError::Join(JoinError::Cancelled)),
+        "{error:?}"
+    );
+    assert!(!error.is_timeout());
+}
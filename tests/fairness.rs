@@ -0,0 +1,102 @@
+mod common;
+
+use a_runtime::{
+    http::HttpClient,
+    runtime::{self, Executor, SchedulingPolicy},
+};
+use std::{
+    cell::RefCell,
+    io::Write,
+    net::TcpListener,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+const REQUESTS: usize = 50;
+
+/// Spawns `REQUESTS` gets one after the other under `policy` and returns
+/// the order they completed in. The server responds only once every request
+/// is in and the client has nothing else left to poll, and the tasks are
+/// polled only once every response has woken its task, so the order is down
+/// to the reactor and the `policy` alone.
+fn completion_order(policy: SchedulingPolicy) -> Vec<usize> {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let received = Arc::new(AtomicUsize::new(0));
+    let (go, respond) = mpsc::channel();
+    let (written_tx, written) = mpsc::channel();
+    let server_received = received.clone();
+    let server = thread::spawn(move || {
+        let mut streams = vec![];
+        for _ in 0..REQUESTS {
+            let (mut stream, _) = listener.accept().unwrap();
+            common::read_head(&mut stream);
+            streams.push(stream);
+            server_received.fetch_add(1, Ordering::SeqCst);
+        }
+        respond.recv().unwrap();
+        for stream in &mut streams {
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+        }
+        written_tx.send(()).unwrap();
+        streams
+    });
+
+    let completed = Rc::new(RefCell::new(vec![]));
+    let order = completed.clone();
+    common::executor(policy).block_on(async move {
+        let client = HttpClient::new(&addr);
+        let mut handles = vec![];
+        for i in 0..REQUESTS {
+            let get = client.get("/");
+            let order = order.clone();
+            handles.push(runtime::spawn(async move {
+                assert_eq!(get.await.unwrap(), "ok");
+                order.borrow_mut().push(i);
+            }));
+            // A request is written in the same poll that goes on to wait
+            // for the response, so once the server has it the task waits
+            // behind all the ones spawned before
+            while received.load(Ordering::SeqCst) <= i {
+                runtime::yield_now().await;
+            }
+        }
+        // A wake left over from connecting would get its task polled out of
+        // turn once the responses are in, so those are let through first
+        while Executor::new().stats().ready_queue_depth > 0 {
+            runtime::yield_now().await;
+        }
+        go.send(()).unwrap();
+        // Blocks this thread without returning to the executor, so none of
+        // the tasks can be polled before all of them are ready
+        written.recv().unwrap();
+        while Executor::new().stats().ready_queue_depth < REQUESTS {
+            thread::yield_now();
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+    drop(server.join().unwrap());
+
+    let order = completed.borrow().clone();
+    order
+}
+
+#[test]
+fn fifo_completes_concurrent_gets_in_spawn_order() {
+    let expected: Vec<usize> = (0..REQUESTS).collect();
+    assert_eq!(completion_order(SchedulingPolicy::Fifo), expected);
+}
+
+#[test]
+fn lifo_completes_concurrent_gets_in_another_order() {
+    let spawned: Vec<usize> = (0..REQUESTS).collect();
+    assert_ne!(completion_order(SchedulingPolicy::Lifo), spawned);
+}
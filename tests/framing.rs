@@ -0,0 +1,104 @@
+mod common;
+
+use a_runtime::{
+    http::{HttpClient, HttpError, Response},
+    runtime::SchedulingPolicy,
+};
+use std::time::Duration;
+
+fn get(response: &'static [u8]) -> Result<String, HttpError> {
+    let addr = common::serve(move |_| response.to_vec()).to_string();
+    common::executor(SchedulingPolicy::default()).block_on(HttpClient::new(&addr).get("/"))
+}
+
+#[test]
+fn close_before_content_length_is_malformed() {
+    let result = get(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort");
+
+    assert!(
+        matches!(result, Err(HttpError::MalformedResponse(_))),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn close_before_end_of_chunks_is_malformed() {
+    let result = get(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n");
+
+    assert!(
+        matches!(result, Err(HttpError::MalformedResponse(_))),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn body_until_close_is_complete() {
+    let result = get(b"HTTP/1.1 200 OK\r\n\r\nuntil the end");
+
+    assert_eq!(result.unwrap(), "until the end");
+}
+
+#[test]
+fn endless_chunk_size_line_is_too_large() {
+    let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    response.resize(response.len() + 64 * 1024, b'1');
+    let addr = common::serve(move |_| response.clone()).to_string();
+    let request = HttpClient::new(&addr)
+        .request("GET", "/")
+        .max_response_bytes(1024);
+
+    let result = common::executor(SchedulingPolicy::default()).block_on(request.send());
+
+    assert!(
+        matches!(result, Err(HttpError::ResponseTooLarge(1024))),
+        "{result:?}"
+    );
+}
+
+/// Sends a `method` request to a server that answers with `response` and
+/// keeps the connection open, so the body has to end where the framing
+/// says.
+fn send_to_open(method: &str, response: &'static [u8]) -> Result<Response, HttpError> {
+    let addr = common::serve_open(move |_| response.to_vec()).to_string();
+    let request = HttpClient::new(&addr)
+        .request(method, "/")
+        .timeout(Duration::from_secs(2));
+    common::executor(SchedulingPolicy::default()).block_on(request.send_response())
+}
+
+#[test]
+fn no_content_has_no_body() {
+    let response = send_to_open("GET", b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+
+    assert_eq!(response.status, 204);
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn not_modified_has_no_body() {
+    let response = send_to_open(
+        "GET",
+        b"HTTP/1.1 304 Not Modified\r\nContent-Length: 1000\r\n\r\n",
+    )
+    .unwrap();
+
+    assert_eq!(response.status, 304);
+    assert!(response.body.is_empty());
+}
+
+#[test]
+fn informational_has_no_body() {
+    let response = send_to_open("GET", b"HTTP/1.1 101 Switching Protocols\r\n\r\n").unwrap();
+
+    assert_eq!(response.status, 101);
+}
+
+#[test]
+fn head_response_has_no_body() {
+    let response =
+        send_to_open("HEAD", b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\n").unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("content-length"), Some("1000"));
+    assert!(response.body.is_empty());
+}
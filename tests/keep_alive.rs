@@ -0,0 +1,48 @@
+mod common;
+
+use a_runtime::{http::HttpClient, runtime::SchedulingPolicy};
+use std::{
+    io::Write,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn pooled_connection_closed_by_server_is_replaced() {
+    // Answers the first request on every connection and closes it when the
+    // next one arrives, like a server dropping an idle connection just as
+    // the client reuses it.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            accepted.fetch_add(1, Ordering::SeqCst);
+            thread::spawn(move || {
+                common::read_head(&mut stream);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                common::read_head(&mut stream);
+            });
+        }
+    });
+    let client = HttpClient::new(&addr.to_string()).keep_alive(Duration::from_secs(30));
+
+    let (first, second) = common::executor(SchedulingPolicy::default()).block_on(async move {
+        let first = client.get_timeout("/", Duration::from_secs(5)).await;
+        let second = client.get_timeout("/", Duration::from_secs(5)).await;
+        (first, second)
+    });
+
+    assert_eq!(first.unwrap(), "ok");
+    assert_eq!(second.unwrap(), "ok");
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
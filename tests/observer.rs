@@ -0,0 +1,48 @@
+mod common;
+
+use a_runtime::runtime::{self, Observer, SchedulingPolicy};
+use std::{cell::RefCell, rc::Rc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    Begin(usize),
+    End(usize, bool),
+}
+
+/// Records every callback, shared with the test through an `Rc`.
+#[derive(Clone, Default)]
+struct Recorder(Rc<RefCell<Vec<Event>>>);
+
+impl Observer for Recorder {
+    fn on_poll_begin(&self, id: usize) {
+        self.0.borrow_mut().push(Event::Begin(id));
+    }
+
+    fn on_poll_end(&self, id: usize, ready: bool) {
+        self.0.borrow_mut().push(Event::End(id, ready));
+    }
+}
+
+#[test]
+fn pending_then_ready_task_is_observed_in_order() {
+    let recorder = Recorder::default();
+    let mut executor = common::executor(SchedulingPolicy::default());
+    executor.set_observer(recorder.clone());
+
+    // `block_on` runs its future as a task as well, and it's the only one
+    executor.block_on(runtime::yield_now());
+
+    let events = recorder.0.borrow().clone();
+    let Some(&Event::Begin(id)) = events.first() else {
+        panic!("no poll observed: {events:?}");
+    };
+    assert_eq!(
+        events,
+        [
+            Event::Begin(id),
+            Event::End(id, false),
+            Event::Begin(id),
+            Event::End(id, true),
+        ]
+    );
+}
@@ -0,0 +1,64 @@
+//! Shutting down the reactor is for good, so this file holds a single test.
+
+mod common;
+
+use a_runtime::runtime::{net::TcpStream, reactor, SchedulingPolicy};
+use std::{
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Names of the threads of this process, as the OS sees them.
+#[cfg(target_os = "linux")]
+fn thread_names() -> Vec<String> {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("comm")).ok())
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+/// Waits up to a second for `done` to be true, failing the test if it isn't.
+#[cfg(target_os = "linux")]
+fn wait_for(done: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(start.elapsed() < Duration::from_secs(1), "timed out");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn shutdown_wakes_waiting_tasks_and_joins_the_thread() {
+    // Accepts the connection but never sends anything, so the read below
+    // waits on the reactor until it's shut down
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+
+    let mut executor = common::executor(SchedulingPolicy::default());
+    // The thread names itself once it's running
+    #[cfg(target_os = "linux")]
+    wait_for(|| thread_names().contains(&"reactor".to_string()));
+
+    let shutdown = thread::spawn(|| {
+        thread::sleep(Duration::from_millis(50));
+        reactor().shutdown().unwrap();
+    });
+    let start = Instant::now();
+    let result = executor.block_on(async move {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0; 16];
+        stream.read(&mut buf).await
+    });
+    shutdown.join().unwrap();
+
+    assert!(result.is_err(), "{result:?}");
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(reactor().check_running().is_err());
+    #[cfg(target_os = "linux")]
+    assert!(!thread_names().contains(&"reactor".to_string()));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use a_runtime::runtime::{
+    net::TcpStream,
+    sync::mpsc::{self, TrySendError},
+    SchedulingPolicy, SinkExt,
+};
+use std::{
+    future::Future,
+    io::Read,
+    net::TcpListener,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+
+/// Records whether it was woken.
+#[derive(Default)]
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn send_to_full_channel_waits_for_recv() {
+    let (mut tx, mut rx) = mpsc::channel(1);
+    tx.try_send(1).unwrap();
+    assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+
+    let flag = Arc::new(Flag::default());
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut send = pin!(tx.send(2));
+    assert!(send.as_mut().poll(&mut cx).is_pending());
+    assert!(send.as_mut().poll(&mut cx).is_pending());
+    assert!(!flag.0.load(Ordering::SeqCst));
+
+    assert_eq!(pin!(rx.recv()).poll(&mut cx), Poll::Ready(Some(1)));
+    assert!(flag.0.load(Ordering::SeqCst));
+    assert_eq!(send.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+    assert_eq!(pin!(rx.recv()).poll(&mut cx), Poll::Ready(Some(2)));
+}
+
+#[test]
+fn dropping_receiver_wakes_blocked_sender() {
+    let (mut tx, rx) = mpsc::channel(1);
+    tx.try_send(1).unwrap();
+
+    let flag = Arc::new(Flag::default());
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut send = pin!(tx.send(2));
+    assert!(send.as_mut().poll(&mut cx).is_pending());
+    drop(rx);
+    assert!(flag.0.load(Ordering::SeqCst));
+    match send.as_mut().poll(&mut cx) {
+        Poll::Ready(Err(e)) => assert_eq!(e.0, 2),
+        other => panic!("expected the value back, got {other:?}"),
+    }
+}
+
+#[test]
+fn tcp_stream_sink_writes_in_order() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    let mut executor = common::executor(SchedulingPolicy::default());
+    executor.block_on(async move {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        for chunk in ["GET ", "/ ", "HTTP/1.1"] {
+            stream.send(chunk.as_bytes().to_vec()).await.unwrap();
+        }
+        // Closing flushes the last chunk before shutting down the write half
+        stream.close().await.unwrap();
+    });
+
+    assert_eq!(server.join().unwrap(), b"GET / HTTP/1.1");
+}
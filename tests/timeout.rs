@@ -0,0 +1,42 @@
+mod common;
+
+use a_runtime::{
+    future,
+    runtime::{self, SchedulingPolicy},
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn completed_future_gives_its_output() {
+    let output = common::executor(SchedulingPolicy::default())
+        .block_on_timeout(async { 42 }, Duration::from_secs(5));
+
+    assert_eq!(output, Some(42));
+}
+
+#[test]
+fn never_ready_future_gives_none_at_the_deadline() {
+    let start = Instant::now();
+    let output = common::executor(SchedulingPolicy::default())
+        .block_on_timeout(future::pending::<()>(), Duration::from_millis(50));
+
+    assert_eq!(output, None);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn self_waking_task_gives_none_at_the_deadline() {
+    let start = Instant::now();
+    let output = common::executor(SchedulingPolicy::default()).block_on_timeout(
+        async {
+            loop {
+                runtime::yield_now().await;
+            }
+        },
+        Duration::from_millis(50),
+    );
+
+    assert_eq!(output, None::<()>);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}